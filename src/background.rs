@@ -1,56 +1,85 @@
+use crate::colormap::Colormap;
 use crate::draw::*;
-use crate::function::PerlinNoise;
-use crate::grid::{Dimensions, Grid};
+use crate::function::ScalarField;
+use crate::grid::Grid;
 use crate::isoline::Isolines;
+use crate::range::{Normalization, ValueRange};
+use crate::raster::{Canvas, Rasterize};
+#[cfg(feature = "gpu")]
+use crate::viewport::Viewport;
+#[cfg(feature = "gpu")]
 use glium::Display;
 
-pub const GRID: Grid = Grid {
-    x0: -1.0,
-    x1: 1.0,
-    y0: -1.0,
-    y1: 1.0,
-    dimensions: Dimensions { w: 4, h: 4 },
-};
+pub fn get_color(colormap: Colormap, normalization: &Normalization, value: f32) -> Color {
+    Color {
+        color: colormap.get_color(normalization.normalize(value)),
+    }
+}
+
+/// Hillshading of the heatmap, as if the field were a terrain lit by a light at
+/// `azimuth` degrees clockwise from up and `altitude` degrees above the plane.
+#[derive(Clone, Copy, Debug)]
+pub struct Relief {
+    pub azimuth: f32,
+    pub altitude: f32,
+    /// How much the shading darkens the colormap, from 0 (not at all) to 1.
+    pub strength: f32,
+    /// Height of the terrain per unit of value, relative to world coordinates.
+    pub exaggeration: f32,
+}
+
+impl Relief {
+    pub fn shade(&self, gradient: (f32, f32), color: Color) -> Color {
+        let (azimuth, altitude) = (self.azimuth.to_radians(), self.altitude.to_radians());
+        let light = [
+            azimuth.sin() * altitude.cos(),
+            azimuth.cos() * altitude.cos(),
+            altitude.sin(),
+        ];
+        // the normal of the surface z = value is (-gx, -gy, 1) normalized
+        let gradient = (
+            gradient.0 * self.exaggeration,
+            gradient.1 * self.exaggeration,
+        );
+        let length = (gradient.0 * gradient.0 + gradient.1 * gradient.1 + 1.0).sqrt();
+        let lit = (-gradient.0 * light[0] - gradient.1 * light[1] + light[2]) / length;
+        let factor = 1.0 - self.strength + self.strength * lit.max(0.0);
+        Color {
+            color: color.color.map(|channel| channel * factor),
+        }
+    }
+}
 
 pub struct Background {
     pub grid: Grid,
     colors: Vec<Color>,
+    shape: Vec<Vertex>,
+    indices: Vec<u32>,
+    #[cfg(feature = "gpu")]
+    buffers: Option<BackgroundBuffers>,
+}
+
+#[cfg(feature = "gpu")]
+struct BackgroundBuffers {
     vertices: glium::VertexBuffer<Vertex>,
     indices: glium::IndexBuffer<u32>,
     program: glium::Program,
 }
 
-impl Background {
-    pub fn new(grid: Grid, display: &Display) -> Self {
-        let mut shape = Vec::new();
-        let mut indices = Vec::new();
-        for (x, y) in grid.iterator(true) {
-            shape.push(Vertex { position: [x, y] });
-        }
-        assert!(shape.len() == ((grid.dimensions.h + 1) * (grid.dimensions.w + 1)) as usize);
-        for idx in 0..shape.len() {
-            if idx as i32 / (grid.dimensions.h + 1) == grid.dimensions.w {
-                continue;
-            }
-            if idx as i32 % (grid.dimensions.h + 1) == grid.dimensions.h {
-                continue;
-            }
-            indices.push(idx as u32);
-            indices.push((idx + 1 + grid.dimensions.h as usize) as u32);
-            indices.push((idx + 2 + grid.dimensions.h as usize) as u32);
-            indices.push(idx as u32);
-            indices.push((idx + 2 + grid.dimensions.h as usize) as u32);
-            indices.push((idx + 1_usize) as u32);
-        }
+#[cfg(feature = "gpu")]
+impl BackgroundBuffers {
+    fn new(display: &Display, shape: &[Vertex], indices: &[u32]) -> Self {
         let vertex_shader = r#"
         #version 330
         
         in vec2 position;
+        
+        uniform vec4 view;
         in vec3 color;
         out vec4 fragColor;
         
         void main() {
-            gl_Position = vec4(position, 0.0, 1.0);
+            gl_Position = vec4((position - view.xy) * view.zw, 0.0, 1.0);
             fragColor.rgb = color;
             fragColor.a = 1;
         }
@@ -69,43 +98,109 @@ impl Background {
         let program =
             glium::Program::from_source(display, vertex_shader, fragment_shader, None).unwrap();
 
-        Background {
-            grid,
-            colors: Vec::new(),
-            vertices: glium::VertexBuffer::new(display, &shape).unwrap(),
+        BackgroundBuffers {
+            vertices: glium::VertexBuffer::new(display, shape).unwrap(),
             indices: glium::IndexBuffer::new(
                 display,
                 glium::index::PrimitiveType::TrianglesList,
-                &indices,
+                indices,
             )
             .unwrap(),
             program,
         }
     }
+}
 
-    pub fn process(&mut self, function: &PerlinNoise, isolines: &mut Isolines) {
-        self.colors
-            .reserve(((self.grid.dimensions.w + 1) * (self.grid.dimensions.h + 1)) as usize);
-        for (x, y) in self.grid.iterator(true) {
-            let value = function.get_value(x, y, &self.grid);
-            self.colors.push(Color {
-                color: [value + 0.4, 0.2, 0.05],
-            });
+impl Background {
+    pub fn new(grid: Grid) -> Self {
+        let mut shape = Vec::new();
+        let mut indices = Vec::new();
+        for (x, y) in grid.iterator(true) {
+            shape.push(Vertex { position: [x, y] });
+        }
+        assert!(shape.len() == ((grid.dimensions.h + 1) * (grid.dimensions.w + 1)) as usize);
+        for idx in 0..shape.len() {
+            if idx as i32 / (grid.dimensions.h + 1) == grid.dimensions.w {
+                continue;
+            }
+            if idx as i32 % (grid.dimensions.h + 1) == grid.dimensions.h {
+                continue;
+            }
+            indices.push(idx as u32);
+            indices.push((idx + 1 + grid.dimensions.h as usize) as u32);
+            indices.push((idx + 2 + grid.dimensions.h as usize) as u32);
+            indices.push(idx as u32);
+            indices.push((idx + 2 + grid.dimensions.h as usize) as u32);
+            indices.push((idx + 1_usize) as u32);
+        }
+
+        Background {
+            grid,
+            colors: Vec::new(),
+            shape,
+            indices,
+            #[cfg(feature = "gpu")]
+            buffers: None,
+        }
+    }
+
+    pub fn process(
+        &mut self,
+        function: &dyn ScalarField,
+        colormap: Colormap,
+        range: ValueRange,
+        relief: Option<Relief>,
+        isolines: &mut Isolines,
+    ) {
+        let values: Vec<f32> = self
+            .grid
+            .iterator(true)
+            .map(|(x, y)| function.get_value(x, y))
+            .collect();
+        let normalization = range.resolve(&values);
+        let colors = values
+            .iter()
+            .map(|value| get_color(colormap, &normalization, *value));
+        match relief {
+            Some(relief) => self.colors.extend(
+                colors
+                    .zip(self.grid.iterator(true))
+                    .map(|(color, (x, y))| relief.shade(function.get_gradient(x, y), color)),
+            ),
+            None => self.colors.extend(colors),
         }
 
         isolines.process(&self.grid, function);
     }
 }
 
+#[cfg(feature = "gpu")]
 impl Draw for Background {
-    fn draw(&mut self, display: &mut Display, target: &mut glium::Frame) {
+    fn draw(&mut self, display: &mut Display, target: &mut glium::Frame, viewport: &Viewport) {
+        let buffers = self
+            .buffers
+            .get_or_insert_with(|| BackgroundBuffers::new(display, &self.shape, &self.indices));
         draw_squares(
             target,
-            &self.vertices,
+            &buffers.vertices,
             &glium::VertexBuffer::new(display, &self.colors).unwrap(),
-            &self.indices,
-            &self.program,
+            &buffers.indices,
+            &buffers.program,
+            viewport,
         );
         self.colors.clear();
     }
 }
+
+impl Rasterize for Background {
+    fn rasterize(&mut self, canvas: &mut Canvas) {
+        for triangle in self.indices.chunks(3) {
+            let vertices = [0, 1, 2].map(|i| {
+                let idx = triangle[i] as usize;
+                (self.shape[idx].position, self.colors[idx].color)
+            });
+            canvas.fill_triangle(vertices);
+        }
+        self.colors.clear();
+    }
+}
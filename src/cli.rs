@@ -0,0 +1,477 @@
+use std::path::Path;
+use std::time::Duration;
+
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use rand::Rng;
+
+use crate::scene::{
+    AnimationSettings, ColorSettings, GridSettings, IsolineSettings, NoiseSettings,
+    OverlaySettings, ReliefSettings, Scene,
+};
+use hw1_function_heatmap::background::Relief;
+use hw1_function_heatmap::colormap::Colormap;
+use hw1_function_heatmap::function::{DistanceMetric, NoiseKind, WorleyMode};
+use hw1_function_heatmap::grid::Dimensions;
+use hw1_function_heatmap::isoline::Levels;
+use hw1_function_heatmap::range::ValueRange;
+
+/// Largest seed, as TOML integers are signed 64-bit.
+const MAX_SEED: u64 = i64::MAX as u64;
+
+fn parse_dimensions(s: &str) -> Result<Dimensions, String> {
+    let (w, h) = s
+        .split_once('x')
+        .ok_or_else(|| format!("expected <width>x<height>, got '{}'", s))?;
+    let parse = |value: &str| {
+        value
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| format!("'{}' is not a whole number", value))
+    };
+    Ok(Dimensions {
+        w: parse(w)?,
+        h: parse(h)?,
+    })
+}
+
+fn parse_floats<const N: usize>(s: &str) -> Result<[f32; N], String> {
+    let values = s
+        .split(',')
+        .map(|value| {
+            value
+                .trim()
+                .parse::<f32>()
+                .map_err(|_| format!("'{}' is not a number", value))
+        })
+        .collect::<Result<Vec<f32>, String>>()?;
+    values
+        .try_into()
+        .map_err(|_| format!("expected {} comma separated numbers, got '{}'", N, s))
+}
+
+/// Animated Perlin noise heatmap with isolines.
+///
+/// Keys: UP/DOWN change the grid resolution, +/- the number of isolines,
+/// LEFT/RIGHT seek the animation by a second, SPACE pauses, B toggles bands,
+/// R toggles relief, V toggles the lattice vectors, L the lattice lines, N
+/// cycles noises, W and M the Worley modes and distance metrics, C cycles
+/// colormaps and S saves the scene.
+///
+/// Options given on the command line take precedence over the scene file.
+#[derive(Parser)]
+#[command(version)]
+pub struct Args {
+    /// Scene to start from (if it exists) and to save to with S
+    #[arg(long, value_name = "FILE", default_value = "scene.toml")]
+    pub scene: String,
+
+    /// Window size (or image size with --png/--svg) in pixels
+    #[arg(long, value_name = "WxH", default_value = "1000x1000", value_parser = parse_dimensions)]
+    pub window: Dimensions,
+
+    /// Number of heatmap cells
+    #[arg(long, value_name = "WxH", default_value = "100x100", value_parser = parse_dimensions)]
+    pub grid: Dimensions,
+
+    /// Part of the plane to show, widened to the aspect ratio of the window
+    #[arg(long, value_name = "X0,X1,Y0,Y1", allow_hyphen_values = true, default_value = "-1,1,-1,1", value_parser = parse_floats::<4>)]
+    pub extent: [f32; 4],
+
+    /// Number of noise lattice cells
+    #[arg(long, value_name = "WxH", default_value = "4x4", value_parser = parse_dimensions)]
+    pub lattice: Dimensions,
+
+    /// Number of isolines at quantiles of the values
+    #[arg(long, default_value_t = 5)]
+    pub isolines: usize,
+
+    /// Isoline levels: quantile:COUNT, uniform:COUNT:MIN:MAX, interval:STEP[:BASE] or list:VALUE,VALUE,...
+    #[arg(
+        long,
+        value_name = "SPEC",
+        allow_hyphen_values = true,
+        conflicts_with = "isolines"
+    )]
+    pub levels: Option<Levels>,
+
+    /// Time between frames in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 100)]
+    pub frame_interval: u64,
+
+    /// How much faster than real time the field changes
+    #[arg(long, default_value_t = 1.0)]
+    pub speed: f32,
+
+    /// Animation time in seconds to start from
+    #[arg(long, value_name = "SECONDS", default_value_t = 0.0)]
+    pub time: f32,
+
+    /// Color behind the heatmap, each component in [0, 1]
+    #[arg(long, value_name = "R,G,B", default_value = "0,0.2,0.05", value_parser = parse_floats::<3>)]
+    pub clear_color: [f32; 3],
+
+    /// Seed for a reproducible field, up to 2^63 - 1 [default: random]
+    #[arg(long, value_parser = clap::value_parser!(u64).range(..=MAX_SEED))]
+    pub seed: Option<u64>,
+
+    /// Noise: perlin (rotating gradients), improved (Ken Perlin's permutation table),
+    /// simplex, opensimplex (OpenSimplex2), worley (cellular) or value
+    #[arg(long, default_value_t = NoiseKind::Perlin)]
+    pub noise: NoiseKind,
+
+    /// Distance shown by worley noise: f1 (nearest point), f2 (second nearest) or f2-f1
+    #[arg(long, value_name = "MODE", default_value_t = WorleyMode::F1)]
+    pub worley: WorleyMode,
+
+    /// Distance metric of worley noise: euclidean, manhattan or chebyshev
+    #[arg(long, default_value_t = DistanceMetric::Euclidean)]
+    pub metric: DistanceMetric,
+
+    /// Number of summed noise lattices
+    #[arg(long, default_value_t = 1)]
+    pub octaves: usize,
+
+    /// How much finer every next octave is
+    #[arg(long, default_value_t = 2.0)]
+    pub lacunarity: f32,
+
+    /// How much weaker every next octave is
+    #[arg(long, default_value_t = 0.5)]
+    pub gain: f32,
+
+    /// Wrap the noise lattice so that the field tiles seamlessly with period [-1, 1]²
+    #[arg(long)]
+    pub tileable: bool,
+
+    /// Colormap: viridis, magma, inferno, plasma, cividis, turbo or grayscale
+    #[arg(long, default_value_t = Colormap::Viridis)]
+    pub colormap: Colormap,
+
+    /// Fixed range of values mapped to the colormap [default: range of each frame]
+    #[arg(long, value_name = "MIN,MAX", allow_hyphen_values = true, value_parser = parse_floats::<2>, conflicts_with = "clip")]
+    pub range: Option<[f32; 2]>,
+
+    /// Percentage of the lowest and the highest values ignored when finding the range
+    #[arg(long, value_name = "PERCENT", default_value_t = 0.0)]
+    pub clip: f32,
+
+    /// Start with the heatmap shaded as a lit terrain
+    #[arg(long)]
+    pub relief: bool,
+
+    /// Direction the relief is lit from, in degrees clockwise from up
+    #[arg(long, value_name = "DEGREES", default_value_t = 315.0)]
+    pub light_azimuth: f32,
+
+    /// Height of the light above the plane, in degrees
+    #[arg(long, value_name = "DEGREES", default_value_t = 45.0)]
+    pub light_altitude: f32,
+
+    /// How much the relief shading darkens the colormap, from 0 to 1
+    #[arg(long, default_value_t = 0.8)]
+    pub relief_strength: f32,
+
+    /// Height of the relief per unit of value, relative to the width of the plane
+    #[arg(long, default_value_t = 0.25)]
+    pub relief_exaggeration: f32,
+
+    /// Show the gradient vectors of the noise lattice as arrows
+    #[arg(long)]
+    pub arrows: bool,
+
+    /// Show the lines of the noise lattice
+    #[arg(long)]
+    pub lattice_lines: bool,
+
+    /// Start with filled bands between the isolines instead of the heatmap
+    #[arg(long)]
+    pub bands: bool,
+
+    /// Render a single frame to a PNG file instead of opening a window; with
+    /// --frames every frame is numbered before the extension, like frame_0001.png
+    #[arg(long, value_name = "FILE")]
+    pub png: Option<String>,
+
+    /// Write the isolines to an SVG file instead of opening a window
+    #[arg(long, value_name = "FILE")]
+    pub svg: Option<String>,
+
+    /// Write the isolines (and bands with --bands) to a GeoJSON file instead of opening a window
+    #[arg(long, value_name = "FILE")]
+    pub geojson: Option<String>,
+
+    /// Render the frames into an animated GIF instead of opening a window
+    #[arg(long, value_name = "FILE")]
+    pub gif: Option<String>,
+
+    /// Number of frames to render headlessly
+    #[arg(long, default_value_t = 1)]
+    pub frames: usize,
+
+    /// Frames per second of the rendered animation
+    #[arg(long, default_value_t = 10.0)]
+    pub fps: f32,
+}
+
+impl Args {
+    /// Parses the command line, exiting with a usage error for values that are
+    /// well-formed but don't make sense together. Values not given on the
+    /// command line are taken from the scene file when there is one.
+    pub fn parse_and_validate() -> Self {
+        let matches = Self::command().get_matches();
+        let mut args = Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        let explicit = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        if Path::new(&args.scene).exists() {
+            match Scene::load(&args.scene) {
+                Ok(scene) => args.apply_scene(scene, explicit),
+                Err(message) => Self::command().error(ErrorKind::Io, message).exit(),
+            }
+        }
+        // a concrete seed lets S save a scene that reproduces this run
+        args.seed
+            .get_or_insert_with(|| rand::thread_rng().gen_range(0..=MAX_SEED));
+        if let Err(message) = args.validate() {
+            Self::command()
+                .error(ErrorKind::ValueValidation, message)
+                .exit();
+        }
+        args
+    }
+
+    /// The arguments of a command line without any options.
+    pub fn defaults() -> Self {
+        Self::parse_from([env!("CARGO_PKG_NAME")])
+    }
+
+    fn apply_scene(&mut self, scene: Scene, explicit: impl Fn(&str) -> bool) {
+        if !explicit("noise") {
+            self.noise = scene.noise.kind;
+        }
+        if !explicit("seed") && scene.noise.seed.is_some() {
+            self.seed = scene.noise.seed;
+        }
+        if !explicit("lattice") {
+            self.lattice = scene.noise.lattice;
+        }
+        if !explicit("octaves") {
+            self.octaves = scene.noise.octaves;
+        }
+        if !explicit("lacunarity") {
+            self.lacunarity = scene.noise.lacunarity;
+        }
+        if !explicit("gain") {
+            self.gain = scene.noise.gain;
+        }
+        if !explicit("tileable") {
+            self.tileable = scene.noise.tileable;
+        }
+        if !explicit("worley") {
+            self.worley = scene.noise.worley;
+        }
+        if !explicit("metric") {
+            self.metric = scene.noise.metric;
+        }
+        if !explicit("extent") {
+            self.extent = scene.grid.extent;
+        }
+        if !explicit("grid") {
+            self.grid = scene.grid.cells;
+        }
+        if !explicit("isolines") && !explicit("levels") {
+            self.levels = Some(scene.isolines.levels);
+        }
+        if !explicit("bands") {
+            self.bands = scene.isolines.bands;
+        }
+        if !explicit("colormap") {
+            self.colormap = scene.colors.colormap;
+        }
+        // --range and --clip replace each other
+        if !explicit("range") && !explicit("clip") {
+            self.range = scene.colors.range;
+            self.clip = scene.colors.clip;
+        }
+        if !explicit("clear_color") {
+            self.clear_color = scene.colors.clear_color;
+        }
+        if !explicit("relief") {
+            self.relief = scene.relief.enabled;
+        }
+        if !explicit("light_azimuth") {
+            self.light_azimuth = scene.relief.azimuth;
+        }
+        if !explicit("light_altitude") {
+            self.light_altitude = scene.relief.altitude;
+        }
+        if !explicit("relief_strength") {
+            self.relief_strength = scene.relief.strength;
+        }
+        if !explicit("relief_exaggeration") {
+            self.relief_exaggeration = scene.relief.exaggeration;
+        }
+        if !explicit("arrows") {
+            self.arrows = scene.overlay.arrows;
+        }
+        if !explicit("lattice_lines") {
+            self.lattice_lines = scene.overlay.lattice_lines;
+        }
+        if !explicit("speed") {
+            self.speed = scene.animation.speed;
+        }
+        if !explicit("time") {
+            self.time = scene.animation.time;
+        }
+        if !explicit("frame_interval") {
+            self.frame_interval = scene.animation.frame_interval;
+        }
+    }
+
+    /// The current settings as a scene that reproduces them.
+    pub fn to_scene(&self) -> Scene {
+        Scene {
+            noise: NoiseSettings {
+                kind: self.noise,
+                seed: self.seed,
+                lattice: self.lattice,
+                octaves: self.octaves,
+                lacunarity: self.lacunarity,
+                gain: self.gain,
+                tileable: self.tileable,
+                worley: self.worley,
+                metric: self.metric,
+            },
+            grid: GridSettings {
+                extent: self.extent,
+                cells: self.grid,
+            },
+            isolines: IsolineSettings {
+                levels: self.get_levels(),
+                bands: self.bands,
+            },
+            colors: ColorSettings {
+                colormap: self.colormap,
+                range: self.range,
+                clip: self.clip,
+                clear_color: self.clear_color,
+            },
+            relief: ReliefSettings {
+                enabled: self.relief,
+                azimuth: self.light_azimuth,
+                altitude: self.light_altitude,
+                strength: self.relief_strength,
+                exaggeration: self.relief_exaggeration,
+            },
+            overlay: OverlaySettings {
+                arrows: self.arrows,
+                lattice_lines: self.lattice_lines,
+            },
+            animation: AnimationSettings {
+                speed: self.speed,
+                time: self.time,
+                frame_interval: self.frame_interval,
+            },
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (name, dimensions) in [
+            ("--window", self.window),
+            ("--grid", self.grid),
+            ("--lattice", self.lattice),
+        ] {
+            if dimensions.w <= 0 || dimensions.h <= 0 {
+                return Err(format!("{} must be positive in both directions", name));
+            }
+        }
+        let [x0, x1, y0, y1] = self.extent;
+        if x0 >= x1 || y0 >= y1 {
+            return Err("--extent must have X0 < X1 and Y0 < Y1".to_string());
+        }
+        if !self.speed.is_finite() || !self.time.is_finite() {
+            return Err("--speed and --time must be finite".to_string());
+        }
+        self.get_levels()
+            .validate()
+            .map_err(|message| format!("--levels: {}", message))?;
+        if self.frames == 0 {
+            return Err("--frames must be at least 1".to_string());
+        }
+        if !(self.fps > 0.0 && self.fps <= 100.0) {
+            return Err("--fps must be within (0, 100]".to_string());
+        }
+        if self.gif.is_some()
+            && (self.window.w > u16::MAX as i32 || self.window.h > u16::MAX as i32)
+        {
+            return Err(format!("--window can't exceed {0}x{0} for --gif", u16::MAX));
+        }
+        if self.frame_interval == 0 {
+            return Err("--frame-interval must be positive".to_string());
+        }
+        if self.clear_color.iter().any(|c| !(0.0..=1.0).contains(c)) {
+            return Err("--clear-color components must be within [0, 1]".to_string());
+        }
+        if self.octaves == 0 {
+            return Err("--octaves must be at least 1".to_string());
+        }
+        if self.lacunarity <= 0.0 || self.gain <= 0.0 {
+            return Err("--lacunarity and --gain must be positive".to_string());
+        }
+        if let Some([min, max]) = self.range {
+            if min >= max {
+                return Err(format!(
+                    "--range minimum {} must be below maximum {}",
+                    min, max
+                ));
+            }
+        }
+        if self.relief_exaggeration.is_nan() || self.relief_exaggeration <= 0.0 {
+            return Err("--relief-exaggeration must be positive".to_string());
+        }
+        if !(self.light_altitude > 0.0 && self.light_altitude <= 90.0) {
+            return Err("--light-altitude must be within (0, 90]".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.relief_strength) {
+            return Err("--relief-strength must be within [0, 1]".to_string());
+        }
+        if !(0.0..50.0).contains(&self.clip) {
+            return Err("--clip must be within [0, 50)".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn get_range(&self) -> ValueRange {
+        match self.range {
+            Some([min, max]) => ValueRange::Fixed { min, max },
+            None => ValueRange::Auto { clip: self.clip },
+        }
+    }
+
+    pub fn get_levels(&self) -> Levels {
+        self.levels.clone().unwrap_or(Levels::Quantile {
+            count: self.isolines,
+        })
+    }
+
+    pub fn get_relief(&self) -> Option<Relief> {
+        self.relief.then_some(Relief {
+            azimuth: self.light_azimuth,
+            altitude: self.light_altitude,
+            strength: self.relief_strength,
+            exaggeration: self.relief_exaggeration,
+        })
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed.expect("the seed is chosen when parsing")
+    }
+
+    pub fn get_frame_interval(&self) -> Duration {
+        Duration::from_millis(self.frame_interval)
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.png.is_some() || self.svg.is_some() || self.geojson.is_some() || self.gif.is_some()
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Colormap {
+    Viridis,
+    Magma,
+    Inferno,
+    Plasma,
+    Cividis,
+    Turbo,
+    Grayscale,
+}
+
+// Polynomial fits of the matplotlib colormaps, coefficients from t^0 to t^6.
+const VIRIDIS: [[f32; 3]; 7] = [
+    [0.277_727_33, 0.005_407_344_5, 0.334_099_8],
+    [0.105_093_04, 1.404_613_5, 1.384_590_2],
+    [-0.330_861_83, 0.214_847_56, 0.095_095_16],
+    [-4.634_230_4, -5.799_101, -19.332_441],
+    [6.228_27, 14.179_933, 56.690_55],
+    [4.776_385, -13.745_145, -65.353_03],
+    [-5.435_456, 4.645_852_6, 26.312_435],
+];
+
+const MAGMA: [[f32; 3]; 7] = [
+    [-0.002_136_485, -0.000_749_655, -0.005_386_128],
+    [0.251_660_54, 0.677_523_2, 2.494_026_6],
+    [8.353_717, -3.577_719_5, 0.314_467_9],
+    [-27.668_733, 14.264_731, -13.649_213],
+    [52.176_14, -27.943_607, 12.944_169],
+    [-50.768_524, 29.046_583, 4.234_153],
+    [18.655_705, -11.489_774, -5.601_961_5],
+];
+
+const INFERNO: [[f32; 3]; 7] = [
+    [0.000_218_940_37, 0.001_651_004_6, -0.019_480_899],
+    [0.106_513_42, 0.563_956_4, 3.932_712_3],
+    [11.602_493, -3.972_854, -15.942_394],
+    [-41.703_995, 17.436_4, 44.354_145],
+    [77.162_94, -33.402_36, -81.807_31],
+    [-71.319_43, 32.626_064, 73.209_52],
+    [25.131_126, -12.242_669, -23.070_326],
+];
+
+const PLASMA: [[f32; 3]; 7] = [
+    [0.058_732_344, 0.023_336_709, 0.543_340_2],
+    [2.176_514_6, 0.238_383_42, 0.753_960_45],
+    [-2.689_460_5, -7.455_851, 3.110_8],
+    [6.130_348, 42.346_188, -28.518_855],
+    [-11.107_436, -82.666_31, 60.139_847],
+    [10.023_066, 71.413_62, -54.072_186],
+    [-3.658_713_8, -22.931_534, 18.191_908],
+];
+
+// Polynomial approximation of Turbo published together with the colormap.
+const TURBO: [[f32; 3]; 6] = [
+    [0.135_721_38, 0.091_402_61, 0.106_673_3],
+    [4.615_392_6, 2.194_188_4, 12.641_946],
+    [-42.660_324, 4.842_966_6, -60.582_047],
+    [132.131_08, -14.185_033, 110.362_77],
+    [-152.942_4, 4.277_298_5, -89.903_11],
+    [59.286_38, 2.829_566, 27.348_25],
+];
+
+// Cividis sampled at t = 0, 1/8, ..., 1 and interpolated linearly.
+const CIVIDIS: [[f32; 3]; 9] = [
+    [0.0, 0.135, 0.305],
+    [0.063, 0.218, 0.434],
+    [0.243, 0.303, 0.425],
+    [0.364, 0.388, 0.44],
+    [0.486, 0.478, 0.471],
+    [0.608, 0.571, 0.469],
+    [0.739, 0.669, 0.441],
+    [0.872, 0.774, 0.385],
+    [0.996, 0.909, 0.217],
+];
+
+fn polynomial<const N: usize>(coefficients: &[[f32; 3]; N], t: f32) -> [f32; 3] {
+    let mut color = [0.0; 3];
+    for coefficient in coefficients.iter().rev() {
+        for channel in 0..3 {
+            color[channel] = color[channel] * t + coefficient[channel];
+        }
+    }
+    color
+}
+
+fn piecewise_linear(stops: &[[f32; 3]], t: f32) -> [f32; 3] {
+    let position = t * (stops.len() - 1) as f32;
+    let idx = (position as usize).min(stops.len() - 2);
+    let frac = position - idx as f32;
+    let mut color = [0.0; 3];
+    for channel in 0..3 {
+        color[channel] = stops[idx][channel] * (1.0 - frac) + stops[idx + 1][channel] * frac;
+    }
+    color
+}
+
+impl Colormap {
+    pub const ALL: [Colormap; 7] = [
+        Colormap::Viridis,
+        Colormap::Magma,
+        Colormap::Inferno,
+        Colormap::Plasma,
+        Colormap::Cividis,
+        Colormap::Turbo,
+        Colormap::Grayscale,
+    ];
+
+    /// Color of `t`, clamped to `[0, 1]`.
+    pub fn get_color(&self, t: f32) -> [f32; 3] {
+        let t = t.clamp(0.0, 1.0);
+        let color = match self {
+            Colormap::Viridis => polynomial(&VIRIDIS, t),
+            Colormap::Magma => polynomial(&MAGMA, t),
+            Colormap::Inferno => polynomial(&INFERNO, t),
+            Colormap::Plasma => polynomial(&PLASMA, t),
+            Colormap::Cividis => piecewise_linear(&CIVIDIS, t),
+            Colormap::Turbo => polynomial(&TURBO, t),
+            Colormap::Grayscale => [t, t, t],
+        };
+        color.map(|channel| channel.clamp(0.0, 1.0))
+    }
+
+    pub fn next(&self) -> Colormap {
+        let idx = Self::ALL.iter().position(|c| c == self).unwrap();
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Colormap::Viridis => "viridis",
+            Colormap::Magma => "magma",
+            Colormap::Inferno => "inferno",
+            Colormap::Plasma => "plasma",
+            Colormap::Cividis => "cividis",
+            Colormap::Turbo => "turbo",
+            Colormap::Grayscale => "grayscale",
+        }
+    }
+}
+
+impl fmt::Display for Colormap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.get_name())
+    }
+}
+
+impl FromStr for Colormap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|colormap| colormap.get_name() == s.to_lowercase())
+            .ok_or_else(|| format!("unknown colormap: {}", s))
+    }
+}
@@ -1,7 +1,7 @@
-use glium::uniforms::EmptyUniforms;
-use glium::Display;
-use glium::Frame;
-use glium::Surface;
+#[cfg(feature = "gpu")]
+use crate::viewport::Viewport;
+#[cfg(feature = "gpu")]
+use glium::{Display, Frame, Surface};
 
 #[derive(Copy, Clone)]
 pub struct Vertex {
@@ -19,44 +19,70 @@ pub struct Color {
     pub color: [f32; 3],
 }
 
+#[cfg(feature = "gpu")]
 glium::implement_vertex!(Vertex, position);
+#[cfg(feature = "gpu")]
 glium::implement_vertex!(Color, color);
+#[cfg(feature = "gpu")]
 glium::implement_vertex!(ColoredVertex, position, color);
 
+#[cfg(feature = "gpu")]
 pub trait Draw {
-    fn draw(&mut self, display: &mut Display, target: &mut Frame);
+    fn draw(&mut self, display: &mut Display, target: &mut Frame, viewport: &Viewport);
 }
 
+#[cfg(feature = "gpu")]
 pub fn draw_squares(
     target: &mut Frame,
     vertex_buffer: &glium::VertexBuffer<Vertex>,
     color_buffer: &glium::VertexBuffer<Color>,
     index_buffer: &glium::IndexBuffer<u32>,
     program: &glium::Program,
+    viewport: &Viewport,
 ) {
     target
         .draw(
             (vertex_buffer, color_buffer),
             index_buffer,
             program,
-            &EmptyUniforms,
+            &glium::uniform! { view: viewport.get_transform() },
             &Default::default(),
         )
         .unwrap();
 }
 
+#[cfg(feature = "gpu")]
 pub fn draw_vectors(
     target: &mut Frame,
     vertex_buffer: &glium::VertexBuffer<Vertex>,
     index_buffer: &glium::IndexBuffer<u32>,
     program: &glium::Program,
+    viewport: &Viewport,
 ) {
     target
         .draw(
             vertex_buffer,
             index_buffer,
             program,
-            &glium::uniforms::EmptyUniforms,
+            &glium::uniform! { view: viewport.get_transform() },
+            &Default::default(),
+        )
+        .unwrap();
+}
+
+#[cfg(feature = "gpu")]
+pub fn draw_triangles(
+    target: &mut Frame,
+    vertex_buffer: &glium::VertexBuffer<ColoredVertex>,
+    program: &glium::Program,
+    viewport: &Viewport,
+) {
+    target
+        .draw(
+            vertex_buffer,
+            glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+            program,
+            &glium::uniform! { view: viewport.get_transform() },
             &Default::default(),
         )
         .unwrap();
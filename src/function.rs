@@ -1,7 +1,12 @@
 use std::f32::consts::PI;
+use std::fmt;
+use std::str::FromStr;
 
 use crate::grid::{Dimensions, Grid};
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug)]
 struct RotatingVector {
@@ -9,25 +14,181 @@ struct RotatingVector {
     rotation_speed: f32,
 }
 
+impl RotatingVector {
+    /// The angle at animation time `time`, starting from `angle` at 0.
+    fn get_angle(&self, time: f32) -> f32 {
+        self.angle + self.rotation_speed * time
+    }
+}
+
+/// A function of world coordinates that `Background` and `Isolines` can visualize.
+/// It is animated by the time in seconds given to `set_time`, and depends on
+/// nothing else, so frames can be computed at any time and in any order.
+pub trait ScalarField {
+    fn get_value(&self, x: f32, y: f32) -> f32;
+    fn get_time(&self) -> f32;
+    fn set_time(&mut self, time: f32);
+
+    /// Advances the animation by `dt` seconds.
+    fn update(&mut self, dt: f32) {
+        self.set_time(self.get_time() + dt);
+    }
+
+    /// Makes a lattice-based field repeat with period `[-1, 1]²`; fields that
+    /// can't tile ignore it.
+    fn set_tileable(&mut self, _tileable: bool) {}
+
+    /// Picks what a cellular field shows; other fields ignore it.
+    fn set_cellular(&mut self, _mode: WorleyMode, _metric: DistanceMetric) {}
+
+    /// The lattice of vectors the field is built from, if it has one to show.
+    fn get_gradient_lattice(&self) -> Option<&dyn GradientLattice> {
+        None
+    }
+
+    /// Partial derivatives of `get_value` by `x` and `y`, by central differences
+    /// unless a field knows them exactly.
+    fn get_gradient(&self, x: f32, y: f32) -> (f32, f32) {
+        let h = 1e-3;
+        (
+            (self.get_value(x + h, y) - self.get_value(x - h, y)) / (2.0 * h),
+            (self.get_value(x, y + h) - self.get_value(x, y - h)) / (2.0 * h),
+        )
+    }
+}
+
+/// Vectors at the nodes of a lattice, as shown by `LatticeOverlay`.
+pub trait GradientLattice {
+    fn get_lattice(&self) -> &Grid;
+
+    /// Lattice nodes within `[x0, x1, y0, y1]` with the current directions of
+    /// their vectors, as unit vectors in lattice cell units.
+    fn get_nodes(&self, extent: [f32; 4]) -> Vec<((f32, f32), (f32, f32))>;
+}
+
+/// The generators a `FractalNoise` can sum octaves of.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoiseKind {
+    /// `PerlinNoise` with rotating gradients.
+    Perlin,
+    /// `ImprovedNoise` with a permutation table.
+    Improved,
+    /// `SimplexNoise`.
+    Simplex,
+    /// `OpenSimplexNoise`, the OpenSimplex2 variant of simplex noise.
+    OpenSimplex,
+    /// `WorleyNoise`, distances to scattered feature points.
+    Worley,
+    /// `ValueNoise`, interpolated random values.
+    Value,
+}
+
+impl NoiseKind {
+    pub const ALL: [NoiseKind; 6] = [
+        NoiseKind::Perlin,
+        NoiseKind::Improved,
+        NoiseKind::Simplex,
+        NoiseKind::OpenSimplex,
+        NoiseKind::Worley,
+        NoiseKind::Value,
+    ];
+
+    pub fn create(&self, dimensions: Dimensions, seed: u64) -> Box<dyn ScalarField> {
+        match self {
+            NoiseKind::Perlin => Box::new(PerlinNoise::with_seed(dimensions, seed)),
+            NoiseKind::Improved => Box::new(ImprovedNoise::with_seed(dimensions, seed)),
+            NoiseKind::Simplex => Box::new(SimplexNoise::with_seed(dimensions, seed)),
+            NoiseKind::OpenSimplex => Box::new(OpenSimplexNoise::with_seed(dimensions, seed)),
+            NoiseKind::Worley => Box::new(WorleyNoise::with_seed(dimensions, seed)),
+            NoiseKind::Value => Box::new(ValueNoise::with_seed(dimensions, seed)),
+        }
+    }
+
+    pub fn next(&self) -> NoiseKind {
+        let idx = Self::ALL.iter().position(|n| n == self).unwrap();
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            NoiseKind::Perlin => "perlin",
+            NoiseKind::Improved => "improved",
+            NoiseKind::Simplex => "simplex",
+            NoiseKind::OpenSimplex => "opensimplex",
+            NoiseKind::Worley => "worley",
+            NoiseKind::Value => "value",
+        }
+    }
+}
+
+impl fmt::Display for NoiseKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.get_name())
+    }
+}
+
+impl FromStr for NoiseKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.get_name() == s.to_lowercase())
+            .ok_or_else(|| format!("unknown noise: {}", s))
+    }
+}
+
+/// A well mixed hash of a lattice node, for nodes outside the lattice to
+/// reuse the ones inside it in a scrambled order.
+fn scramble(x: i32, y: i32) -> usize {
+    let mut hash = (x as u32).wrapping_mul(0x9e37_79b9) ^ (y as u32).wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x7feb_352d);
+    hash ^= hash >> 15;
+    hash as usize
+}
+
+/// Gradient noise on a lattice over `[-1, 1]²`, with values within about `[-0.7, 0.7]`.
+/// Past that square the lattice goes on with the same vectors in a scrambled
+/// order, or repeats itself when the noise is tileable.
 pub struct PerlinNoise {
     vectors: Vec<RotatingVector>,
-    dimensions: Dimensions,
+    lattice: Grid,
+    time: f32,
+    tileable: bool,
 }
 
 const EPS: f32 = 1e-5;
 
 impl PerlinNoise {
     pub fn new(dimensions: Dimensions) -> Self {
+        Self::from_rng(dimensions, &mut rand::thread_rng())
+    }
+
+    pub fn with_seed(dimensions: Dimensions, seed: u64) -> Self {
+        Self::from_rng(dimensions, &mut StdRng::seed_from_u64(seed))
+    }
+
+    fn from_rng<R: Rng>(dimensions: Dimensions, rng: &mut R) -> Self {
         let mut vectors = Vec::new();
         for _ in 0..(dimensions.w + 1) * (dimensions.h + 1) {
             vectors.push(RotatingVector {
-                angle: rand::thread_rng().gen_range(0.0..2.0 * PI),
-                rotation_speed: rand::thread_rng().gen_range(0.0..0.2),
+                angle: rng.gen_range(0.0..2.0 * PI),
+                rotation_speed: rng.gen_range(0.0..2.0),
             });
         }
         Self {
             vectors,
-            dimensions,
+            lattice: Grid {
+                x0: -1.0,
+                x1: 1.0,
+                y0: -1.0,
+                y1: 1.0,
+                dimensions,
+            },
+            time: 0.0,
+            tileable: false,
         }
     }
 
@@ -43,50 +204,1202 @@ impl PerlinNoise {
 
     fn interpolate(a: f32, b: f32, x: f32) -> f32 {
         // return (b - a) * (x) + a;
-        (b - a) * (3.0 * x.powf(2.0) - 2.0 * x.powf(3.0)) + a
+        (b - a) * Self::smoothstep(x) + a
+    }
+
+    fn smoothstep(x: f32) -> f32 {
+        3.0 * x.powf(2.0) - 2.0 * x.powf(3.0)
     }
 
+    fn smoothstep_derivative(x: f32) -> f32 {
+        6.0 * x - 6.0 * x.powf(2.0)
+    }
+
+    /// Dot products of the offsets of `(x, y)` with the vectors at the corners
+    /// of its lattice cell, those vectors, and the position inside the cell.
+    /// Corners go bottom left, bottom right, top left, top right.
+    #[allow(clippy::type_complexity)]
+    fn get_cell(&self, x: f32, y: f32) -> ([f32; 4], [(f32, f32); 4], f32, f32) {
+        let grid = &self.lattice;
+        let xl = ((x - grid.x0 - EPS) / grid.get_cell_width()).floor() as i32;
+        let yl = ((y - grid.y0 - EPS) / grid.get_cell_height()).floor() as i32;
+        let corners = [(xl, yl), (xl + 1, yl), (xl, yl + 1), (xl + 1, yl + 1)];
+        let vectors = corners.map(|coords| {
+            let angle = self.vectors[self.vector_idx(coords.0, coords.1)].get_angle(self.time);
+            (angle.cos(), angle.sin())
+        });
+        let mut deltas = [0.0; 4];
+        for (idx, coords) in corners.iter().enumerate() {
+            let point = grid.get_point(coords.0, coords.1);
+            // offset in lattice cells, so the value doesn't depend on the lattice size
+            let p = (
+                (x - point.0) / grid.get_cell_width(),
+                (y - point.1) / grid.get_cell_height(),
+            );
+            deltas[idx] = Self::dot_product(p, vectors[idx]);
+        }
+        let pl = grid.get_point(xl, yl);
+        let dx = (x - pl.0) / grid.get_cell_width();
+        let dy = (y - pl.1) / grid.get_cell_height();
+        (deltas, vectors, dx, dy)
+    }
+
+    /// Index of node `(x, y)` inside the lattice, which has `h + 1` nodes per column.
     fn map_idx(&self, x: i32, y: i32) -> i32 {
-        x * self.dimensions.h + y
+        x * (self.lattice.dimensions.h + 1) + y
+    }
+
+    /// Index of the vector at lattice node `(x, y)`, which may lie outside the lattice.
+    fn vector_idx(&self, x: i32, y: i32) -> usize {
+        let dimensions = self.lattice.dimensions;
+        if self.tileable {
+            return self.map_idx(x.rem_euclid(dimensions.w), y.rem_euclid(dimensions.h)) as usize;
+        }
+        if (0..=dimensions.w).contains(&x) && (0..=dimensions.h).contains(&y) {
+            return self.map_idx(x, y) as usize;
+        }
+        scramble(x, y) % self.vectors.len()
     }
 
     fn _map_idx_reverse(&self, idx: i32) -> (i32, i32) {
-        (idx / self.dimensions.h, idx % self.dimensions.h)
+        (
+            idx / (self.lattice.dimensions.h + 1),
+            idx % (self.lattice.dimensions.h + 1),
+        )
+    }
+}
+
+impl ScalarField for PerlinNoise {
+    fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    fn set_time(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    /// Makes the lattice wrap around, so that the vectors on the right and the
+    /// top edges equal those on the left and the bottom ones.
+    fn set_tileable(&mut self, tileable: bool) {
+        self.tileable = tileable;
     }
 
-    pub fn update(&mut self) {
-        for mut vector in &mut self.vectors {
-            vector.angle += vector.rotation_speed;
+    fn get_gradient_lattice(&self) -> Option<&dyn GradientLattice> {
+        Some(self)
+    }
+
+    fn get_value(&self, x: f32, y: f32) -> f32 {
+        let (deltas, _, dx, dy) = self.get_cell(x, y);
+        Self::interpolate(
+            Self::interpolate(deltas[0], deltas[1], dx),
+            Self::interpolate(deltas[2], deltas[3], dx),
+            dy,
+        )
+    }
+
+    fn get_gradient(&self, x: f32, y: f32) -> (f32, f32) {
+        let (deltas, vectors, dx, dy) = self.get_cell(x, y);
+        let width = self.lattice.get_cell_width();
+        let height = self.lattice.get_cell_height();
+        // a delta is linear in (x, y) with the corner vector scaled to world units as slope
+        let slopes = vectors.map(|q| (q.0 / width, q.1 / height));
+        let (sx, sy) = (Self::smoothstep(dx), Self::smoothstep(dy));
+        let (dsx, dsy) = (
+            Self::smoothstep_derivative(dx) / width,
+            Self::smoothstep_derivative(dy) / height,
+        );
+        let bottom = Self::interpolate(deltas[0], deltas[1], dx);
+        let top = Self::interpolate(deltas[2], deltas[3], dx);
+        let bottom_x =
+            slopes[0].0 + sx * (slopes[1].0 - slopes[0].0) + dsx * (deltas[1] - deltas[0]);
+        let bottom_y = slopes[0].1 + sx * (slopes[1].1 - slopes[0].1);
+        let top_x = slopes[2].0 + sx * (slopes[3].0 - slopes[2].0) + dsx * (deltas[3] - deltas[2]);
+        let top_y = slopes[2].1 + sx * (slopes[3].1 - slopes[2].1);
+        (
+            bottom_x + sy * (top_x - bottom_x),
+            bottom_y + sy * (top_y - bottom_y) + dsy * (top - bottom),
+        )
+    }
+}
+
+impl GradientLattice for PerlinNoise {
+    fn get_lattice(&self) -> &Grid {
+        &self.lattice
+    }
+
+    fn get_nodes(&self, extent: [f32; 4]) -> Vec<((f32, f32), (f32, f32))> {
+        let grid = &self.lattice;
+        let [x0, x1, y0, y1] = extent;
+        let first = grid.get_point_rev(x0, y0);
+        let last = grid.get_point_rev(x1, y1);
+        let mut nodes = Vec::new();
+        for i in first.0 - 1..=last.0 + 1 {
+            for j in first.1 - 1..=last.1 + 1 {
+                let point = grid.get_point(i, j);
+                if point.0 < x0 || point.0 > x1 || point.1 < y0 || point.1 > y1 {
+                    continue;
+                }
+                let angle = self.vectors[self.vector_idx(i, j)].get_angle(self.time);
+                nodes.push((point, (angle.cos(), angle.sin())));
+            }
         }
+        nodes
     }
+}
 
-    pub fn get_value(&self, x: f32, y: f32, grid: &Grid) -> f32 {
-        let xl = ((x - grid.x0 - EPS) / ((grid.x1 - grid.x0) / self.dimensions.w as f32)) as i32;
-        let yl = ((y - grid.y0 - EPS) / ((grid.y1 - grid.y0) / self.dimensions.h as f32)) as i32;
-        let mut deltas = vec![];
-        for coords in [(xl, yl), (xl + 1, yl), (xl, yl + 1), (xl + 1, yl + 1)] {
-            let vector = &self.vectors[self.map_idx(coords.0, coords.1) as usize];
-            let point = grid.get_point(
-                coords.0 * grid.dimensions.w / self.dimensions.w,
-                coords.1 * grid.dimensions.h / self.dimensions.h,
-            );
-            let p = (x - point.0, y - point.1);
-            let q = (
-                vector.angle.cos() * 0.5 * ((grid.x1 - grid.x0) / self.dimensions.w as f32),
-                vector.angle.sin() * 0.5 * ((grid.y1 - grid.y0) / self.dimensions.h as f32),
-            );
-            deltas.push(Self::dot_product(p, q));
+// The permutation of Ken Perlin's reference implementation of improved noise.
+const REFERENCE_PERMUTATION: [u8; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225, 140, 36, 103, 30, 69,
+    142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148, 247, 120, 234, 75, 0, 26, 197, 62, 94, 252, 219,
+    203, 117, 35, 11, 32, 57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168, 68, 175,
+    74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122, 60, 211, 133, 230,
+    220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54, 65, 25, 63, 161, 1, 216, 80, 73, 209, 76,
+    132, 187, 208, 89, 18, 169, 200, 196, 135, 130, 116, 188, 159, 86, 164, 100, 109, 198, 173,
+    186, 3, 64, 52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82, 85, 212, 207, 206,
+    59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213, 119, 248, 152, 2, 44, 154, 163,
+    70, 221, 153, 101, 155, 167, 43, 172, 9, 129, 22, 39, 253, 19, 98, 108, 110, 79, 113, 224, 232,
+    178, 185, 112, 104, 218, 246, 97, 228, 251, 34, 242, 193, 238, 210, 144, 12, 191, 179, 162,
+    241, 81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199, 106, 157, 184, 84, 204,
+    176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93, 222, 114, 67, 29, 24, 72, 243, 141,
+    128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+/// A permutation of `0..256` repeated twice, so that nested lookups like
+/// `p[p[i] + j]` need no wrapping.
+struct Permutation(Vec<usize>);
+
+impl Permutation {
+    fn reference() -> Self {
+        Self::from_table(REFERENCE_PERMUTATION)
+    }
+
+    fn with_seed(seed: u64) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        table.shuffle(&mut StdRng::seed_from_u64(seed));
+        Self::from_table(table)
+    }
+
+    fn from_table(table: [u8; 256]) -> Self {
+        Self(
+            table
+                .iter()
+                .chain(table.iter())
+                .map(|p| *p as usize)
+                .collect(),
+        )
+    }
+}
+
+/// Dot product with one of the 12 directions to the edge midpoints of a cube,
+/// picked by the low 4 bits of `hash`, as in the reference improved noise.
+fn grad(hash: usize, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// The default lattice of the permutation-based noises, over `[-1, 1]²`.
+fn unit_lattice(dimensions: Dimensions) -> Grid {
+    Grid {
+        x0: -1.0,
+        x1: 1.0,
+        y0: -1.0,
+        y1: 1.0,
+        dimensions,
+    }
+}
+
+/// World coordinates in lattice cells.
+fn to_lattice(lattice: &Grid, x: f32, y: f32) -> (f32, f32) {
+    (
+        (x - lattice.x0) / lattice.get_cell_width(),
+        (y - lattice.y0) / lattice.get_cell_height(),
+    )
+}
+
+/// How fast the slice of the 3D noises moves along z, in lattice cells per
+/// second: z is the animation time scaled by it.
+const SLICE_SPEED: f32 = 0.5;
+
+/// Ken Perlin's improved noise: gradients out of 12 directions chosen through a
+/// 256-entry permutation table, blended with the quintic fade so that the
+/// second derivative is continuous too. It is 3D, and the field is its slice
+/// at a `z` proportional to the time. `new` uses the reference permutation, so
+/// `noise` matches other implementations; `with_seed` shuffles it.
+pub struct ImprovedNoise {
+    permutation: Permutation,
+    lattice: Grid,
+    time: f32,
+    tileable: bool,
+}
+
+impl ImprovedNoise {
+    pub fn new(dimensions: Dimensions) -> Self {
+        Self::from_permutation(dimensions, Permutation::reference())
+    }
+
+    pub fn with_seed(dimensions: Dimensions, seed: u64) -> Self {
+        Self::from_permutation(dimensions, Permutation::with_seed(seed))
+    }
+
+    fn from_permutation(dimensions: Dimensions, permutation: Permutation) -> Self {
+        Self {
+            permutation,
+            lattice: unit_lattice(dimensions),
+            time: 0.0,
+            tileable: false,
+        }
+    }
+
+    fn fade(t: f32) -> f32 {
+        t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+    }
+
+    fn lerp(t: f32, a: f32, b: f32) -> f32 {
+        a + t * (b - a)
+    }
+
+    /// The lattice column `i` and the next one as indices of the permutation,
+    /// wrapped to the lattice first when tileable. Like in the reference the
+    /// permutation repeats every 256 cells, but a tileable lattice larger than
+    /// that reshuffles each further 256 cells by their high bits, so that the
+    /// gradients don't simply repeat within the tile.
+    fn wrap(&self, i: i32, period: i32) -> (usize, usize) {
+        if self.tileable {
+            let fold = |i: i32| {
+                let i = i.rem_euclid(period) as usize;
+                match i >> 8 {
+                    0 => i,
+                    high => (i & 255) ^ self.permutation.0[high & 255],
+                }
+            };
+            (fold(i), fold(i + 1))
+        } else {
+            (i as usize & 255, (i + 1) as usize & 255)
+        }
+    }
+
+    /// The reference `noise(x, y, z)`, with coordinates in lattice cells.
+    pub fn noise(&self, x: f32, y: f32, z: f32) -> f32 {
+        let p = &self.permutation.0;
+        let (xi, yi, zi) = (x.floor(), y.floor(), z.floor());
+        let (x, y, z) = (x - xi, y - yi, z - zi);
+        let (x0, x1) = self.wrap(xi as i32, self.lattice.dimensions.w);
+        let (y0, y1) = self.wrap(yi as i32, self.lattice.dimensions.h);
+        let (z0, z1) = (zi as i32 as usize & 255, (zi as i32 + 1) as usize & 255);
+        let hash = |i: usize, j: usize, k: usize| p[p[p[i] + j] + k];
+        let (u, v, w) = (Self::fade(x), Self::fade(y), Self::fade(z));
+        Self::lerp(
+            w,
+            Self::lerp(
+                v,
+                Self::lerp(
+                    u,
+                    grad(hash(x0, y0, z0), x, y, z),
+                    grad(hash(x1, y0, z0), x - 1.0, y, z),
+                ),
+                Self::lerp(
+                    u,
+                    grad(hash(x0, y1, z0), x, y - 1.0, z),
+                    grad(hash(x1, y1, z0), x - 1.0, y - 1.0, z),
+                ),
+            ),
+            Self::lerp(
+                v,
+                Self::lerp(
+                    u,
+                    grad(hash(x0, y0, z1), x, y, z - 1.0),
+                    grad(hash(x1, y0, z1), x - 1.0, y, z - 1.0),
+                ),
+                Self::lerp(
+                    u,
+                    grad(hash(x0, y1, z1), x, y - 1.0, z - 1.0),
+                    grad(hash(x1, y1, z1), x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+}
+
+impl ScalarField for ImprovedNoise {
+    fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    fn set_time(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    fn set_tileable(&mut self, tileable: bool) {
+        self.tileable = tileable;
+    }
+
+    fn get_value(&self, x: f32, y: f32) -> f32 {
+        let (u, v) = to_lattice(&self.lattice, x, y);
+        self.noise(u, v, SLICE_SPEED * self.time)
+    }
+}
+
+/// Simplex noise: the sum of radial kernels around the 4 corners of the
+/// tetrahedron containing the point, instead of a blend of the 8 corners of a
+/// cube, so it has fewer axis-aligned artifacts. Like `ImprovedNoise` it is a
+/// moving slice of 3D noise; `new` gives the values of Stefan Gustavson's
+/// reference implementation. Its lattice is skewed, so it can't tile.
+pub struct SimplexNoise {
+    permutation: Permutation,
+    lattice: Grid,
+    time: f32,
+}
+
+impl SimplexNoise {
+    pub fn new(dimensions: Dimensions) -> Self {
+        Self::from_permutation(dimensions, Permutation::reference())
+    }
+
+    pub fn with_seed(dimensions: Dimensions, seed: u64) -> Self {
+        Self::from_permutation(dimensions, Permutation::with_seed(seed))
+    }
+
+    fn from_permutation(dimensions: Dimensions, permutation: Permutation) -> Self {
+        Self {
+            permutation,
+            lattice: unit_lattice(dimensions),
+            time: 0.0,
         }
-        let pl = grid.get_point(
-            xl * grid.dimensions.w / self.dimensions.w,
-            yl * grid.dimensions.h / self.dimensions.h,
+    }
+
+    /// 3D simplex noise with coordinates in lattice cells, within about `[-1, 1]`.
+    pub fn noise(&self, x: f32, y: f32, z: f32) -> f32 {
+        const F3: f32 = 1.0 / 3.0;
+        const G3: f32 = 1.0 / 6.0;
+        let p = &self.permutation.0;
+        // the cell of the skewed lattice, which is made of 6 tetrahedra
+        let s = (x + y + z) * F3;
+        let (i, j, k) = ((x + s).floor(), (y + s).floor(), (z + s).floor());
+        let t = (i + j + k) * G3;
+        let (x0, y0, z0) = (x - (i - t), y - (j - t), z - (k - t));
+        // the second and third corners of the tetrahedron, by the order of the offsets
+        let (corner1, corner2) = if x0 >= y0 {
+            if y0 >= z0 {
+                ((1, 0, 0), (1, 1, 0))
+            } else if x0 >= z0 {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else if y0 < z0 {
+            ((0, 0, 1), (0, 1, 1))
+        } else if x0 < z0 {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        };
+        let (ii, jj, kk) = (
+            i as i32 as usize & 255,
+            j as i32 as usize & 255,
+            k as i32 as usize & 255,
         );
-        let dx = (x - pl.0) / ((grid.x1 - grid.x0) / self.dimensions.w as f32);
-        let dy = (y - pl.1) / ((grid.y1 - grid.y0) / self.dimensions.h as f32);
-        10.0 * Self::interpolate(
-            Self::interpolate(deltas[0], deltas[1], dx),
-            Self::interpolate(deltas[2], deltas[3], dx),
+        [(0, 0, 0), corner1, corner2, (1, 1, 1)]
+            .into_iter()
+            .enumerate()
+            .map(|(n, (di, dj, dk))| {
+                let offset = n as f32 * G3;
+                let (dx, dy, dz) = (
+                    x0 - di as f32 + offset,
+                    y0 - dj as f32 + offset,
+                    z0 - dk as f32 + offset,
+                );
+                let t = 0.6 - dx * dx - dy * dy - dz * dz;
+                if t < 0.0 {
+                    return 0.0;
+                }
+                let hash = p[ii + di + p[jj + dj + p[kk + dk]]] % 12;
+                t * t * t * t * grad(hash, dx, dy, dz)
+            })
+            .sum::<f32>()
+            * 32.0
+    }
+}
+
+impl ScalarField for SimplexNoise {
+    fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    fn set_time(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    fn get_value(&self, x: f32, y: f32) -> f32 {
+        let (u, v) = to_lattice(&self.lattice, x, y);
+        self.noise(u, v, SLICE_SPEED * self.time)
+    }
+}
+
+/// Scales the sum of the kernels of `OpenSimplexNoise` to about `[-1, 1]`.
+const OPEN_SIMPLEX_SCALE: f32 = 32.0;
+
+/// OpenSimplex2 noise: kernels on the body-centered cubic lattice, that is two
+/// cubic lattices offset by half a cell, which avoids the patented parts of 3D
+/// simplex noise. The lattice is rotated so that the slices at constant z,
+/// which the field shows as it moves with time, look the same in every
+/// direction. The gradients come from a permutation table like the ones of
+/// `ImprovedNoise`. It can't tile.
+pub struct OpenSimplexNoise {
+    permutation: Permutation,
+    lattice: Grid,
+    time: f32,
+}
+
+impl OpenSimplexNoise {
+    pub fn new(dimensions: Dimensions) -> Self {
+        Self::from_permutation(dimensions, Permutation::reference())
+    }
+
+    pub fn with_seed(dimensions: Dimensions, seed: u64) -> Self {
+        Self::from_permutation(dimensions, Permutation::with_seed(seed))
+    }
+
+    fn from_permutation(dimensions: Dimensions, permutation: Permutation) -> Self {
+        Self {
+            permutation,
+            lattice: unit_lattice(dimensions),
+            time: 0.0,
+        }
+    }
+
+    /// The gradient direction of node `(i, j, k)` of the first (`l = 0`) or
+    /// the second (`l = 1`) cubic lattice.
+    fn hash(&self, i: i32, j: i32, k: i32, l: usize) -> usize {
+        let p = &self.permutation.0;
+        p[p[p[p[i as usize & 255] + (j as usize & 255)] + (k as usize & 255)] + l]
+    }
+
+    /// 3D OpenSimplex2 noise with coordinates in lattice cells, within about `[-1, 1]`.
+    pub fn noise(&self, x: f32, y: f32, z: f32) -> f32 {
+        // rotate so that z points along the main diagonal of the lattice
+        let xy = x + y;
+        let s2 = xy * -0.211_324_87;
+        let zz = z * 0.577_350_26;
+        let (xr, yr, zr) = (x + s2 + zz, y + s2 + zz, xy * -0.577_350_26 + zz);
+
+        let (mut i, mut j, mut k) = (xr.round() as i32, yr.round() as i32, zr.round() as i32);
+        let (mut dx, mut dy, mut dz) = (xr - i as f32, yr - j as f32, zr - k as f32);
+        // towards the other node of the first lattice that may be in reach
+        let sign = |d: f32| if d >= 0.0 { -1 } else { 1 };
+        let (mut sx, mut sy, mut sz) = (sign(dx), sign(dy), sign(dz));
+        let (mut ax, mut ay, mut az) = (dx.abs(), dy.abs(), dz.abs());
+        let mut a = 0.6 - dx * dx - dy * dy - dz * dz;
+        let mut value = 0.0;
+        for l in 0..2 {
+            // the nearest node of this lattice
+            if a > 0.0 {
+                value += a * a * a * a * grad(self.hash(i, j, k, l), dx, dy, dz);
+            }
+            // and its neighbor along the axis the point is furthest along
+            let b = a + 2.0 * ax.max(ay).max(az) - 1.0;
+            if b > 0.0 {
+                let b4 = b * b * b * b;
+                value += if ax >= ay && ax >= az {
+                    b4 * grad(self.hash(i - sx, j, k, l), dx + sx as f32, dy, dz)
+                } else if ay > ax && ay >= az {
+                    b4 * grad(self.hash(i, j - sy, k, l), dx, dy + sy as f32, dz)
+                } else {
+                    b4 * grad(self.hash(i, j, k - sz, l), dx, dy, dz + sz as f32)
+                };
+            }
+            // move on to the nearest node of the lattice offset by half a cell
+            (ax, ay, az) = (0.5 - ax, 0.5 - ay, 0.5 - az);
+            (dx, dy, dz) = (sx as f32 * ax, sy as f32 * ay, sz as f32 * az);
+            a += (0.75 - ax) - (ay + az);
+            i += (sx < 0) as i32;
+            j += (sy < 0) as i32;
+            k += (sz < 0) as i32;
+            (sx, sy, sz) = (-sx, -sy, -sz);
+        }
+        value * OPEN_SIMPLEX_SCALE
+    }
+}
+
+impl ScalarField for OpenSimplexNoise {
+    fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    fn set_time(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    fn get_value(&self, x: f32, y: f32) -> f32 {
+        let (u, v) = to_lattice(&self.lattice, x, y);
+        self.noise(u, v, SLICE_SPEED * self.time)
+    }
+}
+
+/// Which distances to the feature points `WorleyNoise` shows.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum WorleyMode {
+    /// Distance to the nearest point.
+    #[default]
+    #[serde(rename = "f1")]
+    F1,
+    /// Distance to the second nearest point.
+    #[serde(rename = "f2")]
+    F2,
+    /// Their difference, which is zero along the borders of the Voronoi cells.
+    #[serde(rename = "f2-f1")]
+    F2MinusF1,
+}
+
+impl WorleyMode {
+    pub const ALL: [WorleyMode; 3] = [WorleyMode::F1, WorleyMode::F2, WorleyMode::F2MinusF1];
+
+    pub fn next(&self) -> WorleyMode {
+        let idx = Self::ALL.iter().position(|m| m == self).unwrap();
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            WorleyMode::F1 => "f1",
+            WorleyMode::F2 => "f2",
+            WorleyMode::F2MinusF1 => "f2-f1",
+        }
+    }
+}
+
+impl fmt::Display for WorleyMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.get_name())
+    }
+}
+
+impl FromStr for WorleyMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.get_name() == s.to_lowercase())
+            .ok_or_else(|| format!("unknown Worley mode: {}", s))
+    }
+}
+
+/// How `WorleyNoise` measures the distance to the feature points.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DistanceMetric {
+    /// Straight line distance, giving round cells.
+    #[default]
+    Euclidean,
+    /// Sum of the distances along the axes, giving diamond shaped cells.
+    Manhattan,
+    /// Largest of the distances along the axes, giving square cells.
+    Chebyshev,
+}
+
+impl DistanceMetric {
+    pub const ALL: [DistanceMetric; 3] = [
+        DistanceMetric::Euclidean,
+        DistanceMetric::Manhattan,
+        DistanceMetric::Chebyshev,
+    ];
+
+    pub fn distance(&self, dx: f32, dy: f32) -> f32 {
+        match self {
+            DistanceMetric::Euclidean => (dx * dx + dy * dy).sqrt(),
+            DistanceMetric::Manhattan => dx.abs() + dy.abs(),
+            DistanceMetric::Chebyshev => dx.abs().max(dy.abs()),
+        }
+    }
+
+    /// How many cells around its own a cellular noise searches for the
+    /// nearest points. A point in the same cell can be two cells away in
+    /// Manhattan distance, so a point two cells over may still be nearer.
+    fn search_radius(&self) -> i32 {
+        match self {
+            DistanceMetric::Manhattan => 2,
+            DistanceMetric::Euclidean | DistanceMetric::Chebyshev => 1,
+        }
+    }
+
+    pub fn next(&self) -> DistanceMetric {
+        let idx = Self::ALL.iter().position(|m| m == self).unwrap();
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            DistanceMetric::Euclidean => "euclidean",
+            DistanceMetric::Manhattan => "manhattan",
+            DistanceMetric::Chebyshev => "chebyshev",
+        }
+    }
+}
+
+impl fmt::Display for DistanceMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.get_name())
+    }
+}
+
+impl FromStr for DistanceMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|metric| metric.get_name() == s.to_lowercase())
+            .ok_or_else(|| format!("unknown distance metric: {}", s))
+    }
+}
+
+/// How far a feature point drifts from the center of its orbit, in lattice cells.
+const ORBIT_RADIUS: f32 = 0.2;
+
+/// A feature point of `WorleyNoise`, circling around `center` (relative to
+/// its lattice cell) the way the vectors of `PerlinNoise` rotate.
+#[derive(Debug)]
+struct FeaturePoint {
+    center: (f32, f32),
+    orbit: RotatingVector,
+}
+
+/// Worley (cellular) noise: one feature point in each cell of a lattice over
+/// `[-1, 1]²`, and the value is a distance in lattice cells to the nearest or
+/// the second nearest of them. Its isolines trace the Voronoi cells of the
+/// points. Past that square the points repeat in a scrambled order, or
+/// wrap around when the noise is tileable.
+pub struct WorleyNoise {
+    points: Vec<FeaturePoint>,
+    lattice: Grid,
+    mode: WorleyMode,
+    metric: DistanceMetric,
+    time: f32,
+    tileable: bool,
+}
+
+impl WorleyNoise {
+    pub fn new(dimensions: Dimensions) -> Self {
+        Self::from_rng(dimensions, &mut rand::thread_rng())
+    }
+
+    pub fn with_seed(dimensions: Dimensions, seed: u64) -> Self {
+        Self::from_rng(dimensions, &mut StdRng::seed_from_u64(seed))
+    }
+
+    fn from_rng<R: Rng>(dimensions: Dimensions, rng: &mut R) -> Self {
+        // the orbits stay inside their cells
+        let margin = ORBIT_RADIUS..1.0 - ORBIT_RADIUS;
+        let points = (0..dimensions.w * dimensions.h)
+            .map(|_| FeaturePoint {
+                center: (rng.gen_range(margin.clone()), rng.gen_range(margin.clone())),
+                orbit: RotatingVector {
+                    angle: rng.gen_range(0.0..2.0 * PI),
+                    rotation_speed: rng.gen_range(-2.0..2.0),
+                },
+            })
+            .collect();
+        Self {
+            points,
+            lattice: unit_lattice(dimensions),
+            mode: WorleyMode::default(),
+            metric: DistanceMetric::default(),
+            time: 0.0,
+            tileable: false,
+        }
+    }
+
+    /// The feature point of lattice cell `(x, y)`, in lattice cells.
+    fn get_point(&self, x: i32, y: i32) -> (f32, f32) {
+        let dimensions = self.lattice.dimensions;
+        let idx = if self.tileable {
+            (x.rem_euclid(dimensions.w) * dimensions.h + y.rem_euclid(dimensions.h)) as usize
+        } else if (0..dimensions.w).contains(&x) && (0..dimensions.h).contains(&y) {
+            (x * dimensions.h + y) as usize
+        } else {
+            scramble(x, y) % self.points.len()
+        };
+        let point = &self.points[idx];
+        let (sin, cos) = point.orbit.get_angle(self.time).sin_cos();
+        (
+            x as f32 + point.center.0 + ORBIT_RADIUS * cos,
+            y as f32 + point.center.1 + ORBIT_RADIUS * sin,
+        )
+    }
+}
+
+impl ScalarField for WorleyNoise {
+    fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    fn set_time(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    fn set_tileable(&mut self, tileable: bool) {
+        self.tileable = tileable;
+    }
+
+    fn set_cellular(&mut self, mode: WorleyMode, metric: DistanceMetric) {
+        self.mode = mode;
+        self.metric = metric;
+    }
+
+    fn get_value(&self, x: f32, y: f32) -> f32 {
+        let (u, v) = to_lattice(&self.lattice, x, y);
+        let (cx, cy) = (u.floor() as i32, v.floor() as i32);
+        // the points stay in their cells, so the block of cells within the
+        // search radius around almost always holds the nearest two
+        let r = self.metric.search_radius();
+        let (mut f1, mut f2) = (f32::INFINITY, f32::INFINITY);
+        for i in cx - r..=cx + r {
+            for j in cy - r..=cy + r {
+                let point = self.get_point(i, j);
+                let distance = self.metric.distance(u - point.0, v - point.1);
+                if distance < f1 {
+                    f2 = f1;
+                    f1 = distance;
+                } else if distance < f2 {
+                    f2 = distance;
+                }
+            }
+        }
+        match self.mode {
+            WorleyMode::F1 => f1,
+            WorleyMode::F2 => f2,
+            WorleyMode::F2MinusF1 => f2 - f1,
+        }
+    }
+}
+
+/// Value noise, the baseline gradient noise is compared to: a random value in
+/// `[-1, 1]` at every node of a lattice over `[-1, 1]²`, blended with the same
+/// fade as `PerlinNoise`. The values are sines of angles rotating like the
+/// vectors of `PerlinNoise`, so they oscillate smoothly with time. Past that
+/// square the lattice goes on in a scrambled order, or wraps around when the
+/// noise is tileable.
+pub struct ValueNoise {
+    values: Vec<RotatingVector>,
+    lattice: Grid,
+    time: f32,
+    tileable: bool,
+}
+
+impl ValueNoise {
+    pub fn new(dimensions: Dimensions) -> Self {
+        Self::from_rng(dimensions, &mut rand::thread_rng())
+    }
+
+    pub fn with_seed(dimensions: Dimensions, seed: u64) -> Self {
+        Self::from_rng(dimensions, &mut StdRng::seed_from_u64(seed))
+    }
+
+    fn from_rng<R: Rng>(dimensions: Dimensions, rng: &mut R) -> Self {
+        let values = (0..(dimensions.w + 1) * (dimensions.h + 1))
+            .map(|_| RotatingVector {
+                angle: rng.gen_range(0.0..2.0 * PI),
+                rotation_speed: rng.gen_range(0.0..2.0),
+            })
+            .collect();
+        Self {
+            values,
+            lattice: unit_lattice(dimensions),
+            time: 0.0,
+            tileable: false,
+        }
+    }
+
+    /// The value at lattice node `(x, y)`, which may lie outside the lattice.
+    fn get_node(&self, x: i32, y: i32) -> f32 {
+        let dimensions = self.lattice.dimensions;
+        let idx = if self.tileable {
+            (x.rem_euclid(dimensions.w) * (dimensions.h + 1) + y.rem_euclid(dimensions.h)) as usize
+        } else if (0..=dimensions.w).contains(&x) && (0..=dimensions.h).contains(&y) {
+            (x * (dimensions.h + 1) + y) as usize
+        } else {
+            scramble(x, y) % self.values.len()
+        };
+        self.values[idx].get_angle(self.time).sin()
+    }
+
+    /// The values at the corners of the lattice cell of `(x, y)` and the
+    /// position inside the cell, with the corners ordered like in `PerlinNoise`.
+    fn get_cell(&self, x: f32, y: f32) -> ([f32; 4], f32, f32) {
+        let (u, v) = to_lattice(&self.lattice, x, y);
+        let (xl, yl) = (u.floor(), v.floor());
+        let (i, j) = (xl as i32, yl as i32);
+        let corners = [(i, j), (i + 1, j), (i, j + 1), (i + 1, j + 1)];
+        (corners.map(|(i, j)| self.get_node(i, j)), u - xl, v - yl)
+    }
+}
+
+impl ScalarField for ValueNoise {
+    fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    fn set_time(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    fn set_tileable(&mut self, tileable: bool) {
+        self.tileable = tileable;
+    }
+
+    fn get_value(&self, x: f32, y: f32) -> f32 {
+        let (values, dx, dy) = self.get_cell(x, y);
+        PerlinNoise::interpolate(
+            PerlinNoise::interpolate(values[0], values[1], dx),
+            PerlinNoise::interpolate(values[2], values[3], dx),
             dy,
         )
     }
+
+    fn get_gradient(&self, x: f32, y: f32) -> (f32, f32) {
+        let (values, dx, dy) = self.get_cell(x, y);
+        let bottom = PerlinNoise::interpolate(values[0], values[1], dx);
+        let top = PerlinNoise::interpolate(values[2], values[3], dx);
+        let dsx = PerlinNoise::smoothstep_derivative(dx) / self.lattice.get_cell_width();
+        let dsy = PerlinNoise::smoothstep_derivative(dy) / self.lattice.get_cell_height();
+        let sy = PerlinNoise::smoothstep(dy);
+        let bottom_x = dsx * (values[1] - values[0]);
+        let top_x = dsx * (values[3] - values[2]);
+        (bottom_x + sy * (top_x - bottom_x), dsy * (top - bottom))
+    }
+}
+
+/// Fractal Brownian motion: a sum of noise octaves, each one with a lattice
+/// `lacunarity` times finer and an amplitude `gain` times smaller than the
+/// previous one.
+pub struct FractalNoise {
+    octaves: Vec<Box<dyn ScalarField>>,
+    gain: f32,
+}
+
+impl FractalNoise {
+    pub fn new(dimensions: Dimensions, octaves: usize, lacunarity: f32, gain: f32) -> Self {
+        Self {
+            octaves: Self::octave_dimensions(dimensions, octaves, lacunarity)
+                .map(|dimensions| Box::new(PerlinNoise::new(dimensions)) as Box<dyn ScalarField>)
+                .collect(),
+            gain,
+        }
+    }
+
+    pub fn with_seed(
+        dimensions: Dimensions,
+        octaves: usize,
+        lacunarity: f32,
+        gain: f32,
+        seed: u64,
+    ) -> Self {
+        Self::with_kind(
+            NoiseKind::Perlin,
+            dimensions,
+            octaves,
+            lacunarity,
+            gain,
+            seed,
+        )
+    }
+
+    /// Octaves of `kind`, the `i`-th one seeded with `seed + i` (wrapping around).
+    pub fn with_kind(
+        kind: NoiseKind,
+        dimensions: Dimensions,
+        octaves: usize,
+        lacunarity: f32,
+        gain: f32,
+        seed: u64,
+    ) -> Self {
+        Self {
+            octaves: Self::octave_dimensions(dimensions, octaves, lacunarity)
+                .zip((0..).map(|i| seed.wrapping_add(i)))
+                .map(|(dimensions, seed)| kind.create(dimensions, seed))
+                .collect(),
+            gain,
+        }
+    }
+
+    fn octave_dimensions(
+        dimensions: Dimensions,
+        octaves: usize,
+        lacunarity: f32,
+    ) -> impl Iterator<Item = Dimensions> {
+        (0..octaves.max(1)).map(move |octave| {
+            let scale = lacunarity.powi(octave as i32);
+            Dimensions {
+                w: ((dimensions.w as f32 * scale).round() as i32).max(1),
+                h: ((dimensions.h as f32 * scale).round() as i32).max(1),
+            }
+        })
+    }
+}
+
+impl ScalarField for FractalNoise {
+    fn get_time(&self) -> f32 {
+        self.octaves[0].get_time()
+    }
+
+    /// Sets the same time for every octave.
+    fn set_time(&mut self, time: f32) {
+        for octave in &mut self.octaves {
+            octave.set_time(time);
+        }
+    }
+
+    /// Makes every octave tileable.
+    fn set_tileable(&mut self, tileable: bool) {
+        for octave in &mut self.octaves {
+            octave.set_tileable(tileable);
+        }
+    }
+
+    fn set_cellular(&mut self, mode: WorleyMode, metric: DistanceMetric) {
+        for octave in &mut self.octaves {
+            octave.set_cellular(mode, metric);
+        }
+    }
+
+    /// The lattice of the coarsest octave.
+    fn get_gradient_lattice(&self) -> Option<&dyn GradientLattice> {
+        self.octaves[0].get_gradient_lattice()
+    }
+
+    fn get_value(&self, x: f32, y: f32) -> f32 {
+        let mut amplitude = 1.0;
+        let mut total_amplitude = 0.0;
+        let mut value = 0.0;
+        for octave in &self.octaves {
+            value += amplitude * octave.get_value(x, y);
+            total_amplitude += amplitude;
+            amplitude *= self.gain;
+        }
+        value / total_amplitude
+    }
+
+    fn get_gradient(&self, x: f32, y: f32) -> (f32, f32) {
+        let mut amplitude = 1.0;
+        let mut total_amplitude = 0.0;
+        let mut gradient = (0.0, 0.0);
+        for octave in &self.octaves {
+            let (gx, gy) = octave.get_gradient(x, y);
+            gradient.0 += amplitude * gx;
+            gradient.1 += amplitude * gy;
+            total_amplitude += amplitude;
+            amplitude *= self.gain;
+        }
+        (gradient.0 / total_amplitude, gradient.1 / total_amplitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOLERANCE: f32 = 1e-5;
+
+    fn assert_close(actual: f32, expected: f64) {
+        assert!(
+            (actual as f64 - expected).abs() < TOLERANCE as f64,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)] // 3.14 is the usual test point, not π
+    fn improved_noise_matches_the_reference() {
+        // values of Ken Perlin's Java reference implementation
+        let noise = ImprovedNoise::new(Dimensions { w: 4, h: 4 });
+        assert_close(noise.noise(3.14, 42.0, 7.0), 0.13691995878400012);
+        assert_close(noise.noise(0.5, 0.5, 0.5), -0.25);
+        assert_close(noise.noise(-1.75, 2.25, 0.3), -0.016235000999450674);
+        assert_close(noise.noise(10.3, -4.6, 2.9), -0.00836506738544629);
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn simplex_noise_matches_the_reference() {
+        // values of Stefan Gustavson's Java reference implementation
+        let noise = SimplexNoise::new(Dimensions { w: 4, h: 4 });
+        assert_close(noise.noise(0.3, 0.7, 0.1), 0.009378912921810752);
+        assert_close(noise.noise(3.14, 42.0, 7.0), 0.0021676720133461877);
+        assert_close(noise.noise(-1.75, 2.25, 0.3), -0.128924398);
+        assert_close(noise.noise(10.3, -4.6, 2.9), -0.14342027548971154);
+    }
+
+    fn sample(field: &dyn ScalarField) -> Vec<f32> {
+        (0..50)
+            .map(|i| {
+                let t = i as f32 / 50.0;
+                field.get_value(-1.3 + 2.6 * t, 1.1 - 2.4 * t * t)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn analytic_gradients_match_central_differences() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for kind in [NoiseKind::Perlin, NoiseKind::Value] {
+            for tileable in [false, true] {
+                let mut noise = kind.create(Dimensions { w: 5, h: 4 }, 13);
+                noise.set_tileable(tileable);
+                noise.set_time(0.4);
+                let h = 1e-3;
+                for _ in 0..200 {
+                    // past the lattice too, so tiling wraps negative cells
+                    let x = rng.gen_range(-3.0..3.0);
+                    let y = rng.gen_range(-3.0..3.0);
+                    let (gx, gy) = noise.get_gradient(x, y);
+                    let dx = (noise.get_value(x + h, y) - noise.get_value(x - h, y)) / (2.0 * h);
+                    let dy = (noise.get_value(x, y + h) - noise.get_value(x, y - h)) / (2.0 * h);
+                    for (analytic, numeric) in [(gx, dx), (gy, dy)] {
+                        assert!(
+                            (analytic - numeric).abs() < 1e-2 * numeric.abs().max(1.0),
+                            "{} (tileable: {}) at ({}, {}): {} is not {}",
+                            kind,
+                            tileable,
+                            x,
+                            y,
+                            analytic,
+                            numeric
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tileable_noise_repeats_across_the_lattice() {
+        // the lattice spans [-1, 1]², so a tile is two world units wide
+        let period = 2.0;
+        let tileable = [
+            NoiseKind::Perlin,
+            NoiseKind::Improved,
+            NoiseKind::Worley,
+            NoiseKind::Value,
+        ];
+        for kind in tileable {
+            let mut noise = kind.create(Dimensions { w: 5, h: 4 }, 7);
+            noise.set_tileable(true);
+            noise.set_time(0.6);
+            for i in 0..50 {
+                let t = i as f32 / 50.0;
+                let (x, y) = (-1.3 + 2.6 * t, 1.1 - 2.4 * t * t);
+                let value = noise.get_value(x, y);
+                for shifted in [
+                    noise.get_value(x + period, y),
+                    noise.get_value(x, y + period),
+                ] {
+                    assert!(
+                        (value - shifted).abs() < 1e-4,
+                        "{} at ({}, {}): {} is not {}",
+                        kind,
+                        x,
+                        y,
+                        shifted,
+                        value
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn worley_noise_is_continuous_for_every_metric() {
+        // the block of cells searched moves when a sample crosses a cell edge,
+        // so a point it misses shows up as a jump across that edge
+        let (w, h) = (5, 4);
+        let (width, height) = (2.0 / w as f32, 2.0 / h as f32);
+        let eps = 1e-4;
+        for metric in DistanceMetric::ALL {
+            for mode in [WorleyMode::F1, WorleyMode::F2, WorleyMode::F2MinusF1] {
+                for seed in 0..4 {
+                    let mut noise = WorleyNoise::with_seed(Dimensions { w, h }, seed);
+                    noise.set_cellular(mode, metric);
+                    noise.set_time(seed as f32);
+                    let check = |(x0, y0): (f32, f32), (x1, y1): (f32, f32)| {
+                        let (a, b) = (noise.get_value(x0, y0), noise.get_value(x1, y1));
+                        assert!(
+                            (a - b).abs() < 1e-2,
+                            "{} {} jumps from {} at ({}, {}) to {} at ({}, {})",
+                            metric,
+                            mode,
+                            a,
+                            x0,
+                            y0,
+                            b,
+                            x1,
+                            y1
+                        );
+                    };
+                    for i in 0..=500 {
+                        let t = -2.0 + 4.0 * i as f32 / 500.0;
+                        for k in -w..=2 * w {
+                            let x = -1.0 + k as f32 * width;
+                            check((x - eps, t), (x + eps, t));
+                        }
+                        for k in -h..=2 * h {
+                            let y = -1.0 + k as f32 * height;
+                            check((t, y - eps), (t, y + eps));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn set_time_matches_stepping_with_update() {
+        let dimensions = Dimensions { w: 5, h: 4 };
+        for kind in NoiseKind::ALL {
+            let mut stepped = FractalNoise::with_kind(kind, dimensions, 3, 2.0, 0.5, 11);
+            for _ in 0..25 {
+                stepped.update(0.1);
+            }
+            let mut seeked = FractalNoise::with_kind(kind, dimensions, 3, 2.0, 0.5, 11);
+            seeked.set_time(2.5);
+            for (a, b) in sample(&stepped).into_iter().zip(sample(&seeked)) {
+                assert!((a - b).abs() < 1e-4, "{}: {} is not {}", kind, a, b);
+            }
+            let mut single = kind.create(dimensions, 11);
+            for _ in 0..25 {
+                single.update(0.1);
+            }
+            let mut seeked = kind.create(dimensions, 11);
+            seeked.set_time(2.5);
+            for (a, b) in sample(single.as_ref())
+                .into_iter()
+                .zip(sample(seeked.as_ref()))
+            {
+                assert!((a - b).abs() < 1e-4, "{}: {} is not {}", kind, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn seeking_is_exactly_reproducible() {
+        let dimensions = Dimensions { w: 5, h: 4 };
+        for kind in NoiseKind::ALL {
+            let mut direct = FractalNoise::with_kind(kind, dimensions, 2, 2.0, 0.5, 3);
+            direct.set_time(2.5);
+            let mut seeked = FractalNoise::with_kind(kind, dimensions, 2, 2.0, 0.5, 3);
+            seeked.set_time(7.0);
+            seeked.set_time(-1.0);
+            seeked.set_time(2.5);
+            assert_eq!(sample(&direct), sample(&seeked), "{}", kind);
+        }
+    }
 }
@@ -0,0 +1,73 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::isoband::Band;
+use crate::isoline::Isolines;
+
+/// Positions of a line; GeoJSON closes a ring by repeating its first position.
+fn format_line(points: &[[f32; 2]], closed: bool) -> String {
+    let ring_end = points.first().filter(|_| closed);
+    let positions: Vec<String> = points
+        .iter()
+        .chain(ring_end)
+        .map(|point| format!("[{}, {}]", point[0], point[1]))
+        .collect();
+    format!("[{}]", positions.join(", "))
+}
+
+fn format_bound(bound: f32) -> String {
+    if bound.is_finite() {
+        bound.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+/// Writes the isolines found by the last `Isolines::process` as a GeoJSON
+/// `FeatureCollection` with one `MultiLineString` feature per level. The level
+/// is stored in the `c_value` property; coordinates are world coordinates.
+/// Each of `bands` is added as a `MultiPolygon` feature with `lo`/`hi` properties.
+pub fn write_geojson(
+    path: impl AsRef<Path>,
+    isolines: &Isolines,
+    bands: &[Band],
+) -> std::io::Result<()> {
+    let mut features = Vec::new();
+    for (level, (c, polylines)) in isolines.get_polylines().iter().enumerate() {
+        let lines: Vec<String> = polylines
+            .iter()
+            .map(|polyline| format_line(&polyline.points, polyline.closed))
+            .collect();
+        features.push(format!(
+            r#"{{"type": "Feature", "properties": {{"level": {}, "c_value": {}}}, "geometry": {{"type": "MultiLineString", "coordinates": [{}]}}}}"#,
+            level,
+            c,
+            lines.join(", "),
+        ));
+    }
+    for band in bands {
+        let polygons: Vec<String> = band
+            .polygons
+            .iter()
+            .map(|polygon| {
+                let rings: Vec<String> = std::iter::once(&polygon.exterior)
+                    .chain(&polygon.holes)
+                    .map(|ring| format_line(ring, true))
+                    .collect();
+                format!("[{}]", rings.join(", "))
+            })
+            .collect();
+        features.push(format!(
+            r#"{{"type": "Feature", "properties": {{"lo": {}, "hi": {}}}, "geometry": {{"type": "MultiPolygon", "coordinates": [{}]}}}}"#,
+            format_bound(band.lo),
+            format_bound(band.hi),
+            polygons.join(", "),
+        ));
+    }
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, r#"{{"type": "FeatureCollection", "features": ["#)?;
+    writeln!(out, "  {}", features.join(",\n  "))?;
+    writeln!(out, "]}}")?;
+    out.flush()
+}
@@ -1,4 +1,6 @@
-#[derive(Clone, Copy)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Dimensions {
     pub w: i32,
     pub h: i32,
@@ -16,27 +18,7 @@ pub struct Grid {
 const EPS: f32 = 1e-5;
 
 impl Grid {
-    pub fn new(
-        mut x0: f32,
-        mut x1: f32,
-        mut y0: f32,
-        mut y1: f32,
-        dimensions: Dimensions,
-        window: Dimensions,
-    ) -> Self {
-        let ratio = (y1 - y0) / (x1 - x0);
-        let window_ratio = (window.h as f32) / (window.w as f32);
-
-        if ratio > window_ratio {
-            // image is too high
-            x0 = -window_ratio / ratio;
-            x1 = window_ratio / ratio;
-        }
-        if ratio < window_ratio {
-            // image is too wide
-            y0 = -1.0 / window_ratio * ratio;
-            y1 = 1.0 / window_ratio * ratio;
-        }
+    pub fn new(x0: f32, x1: f32, y0: f32, y1: f32, dimensions: Dimensions) -> Self {
         Self {
             x0,
             x1,
@@ -82,7 +64,7 @@ impl Grid {
 }
 
 impl<'a> Grid {
-    pub fn iterator(&'a self, extra_bound: bool) -> GridIterator {
+    pub fn iterator(&'a self, extra_bound: bool) -> GridIterator<'a> {
         GridIterator {
             idx: 0,
             grid: self,
@@ -0,0 +1,464 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::background::get_color;
+use crate::colormap::Colormap;
+use crate::draw::*;
+use crate::function::ScalarField;
+use crate::grid::Grid;
+use crate::isoline::solve_by_interpolation;
+use crate::range::{Normalization, ValueRange};
+use crate::raster::{Canvas, Rasterize};
+#[cfg(feature = "gpu")]
+use crate::viewport::Viewport;
+#[cfg(feature = "gpu")]
+use glium::{Display, Frame};
+
+const NODE: usize = usize::MAX;
+
+/// Identifies a point independently of the cell it was computed in.
+/// Grid nodes and cell centers are `(id, id, NODE)`; the point where the
+/// segment between nodes `a < b` crosses the `level`-th value is `(a, b, level)`.
+type PointKey = (usize, usize, usize);
+
+#[derive(Clone, Copy)]
+struct BandPoint {
+    key: PointKey,
+    position: [f32; 2],
+    value: f32,
+}
+
+impl BandPoint {
+    fn node(id: usize, position: (f32, f32), value: f32) -> Self {
+        Self {
+            key: (id, id, NODE),
+            position: [position.0, position.1],
+            value,
+        }
+    }
+
+    fn crossing(&self, other: &BandPoint, level: usize, c: f32) -> Self {
+        // both points lie on the same segment between two nodes
+        let ids = [self.key.0, self.key.1, other.key.0, other.key.1];
+        let a = *ids.iter().min().unwrap();
+        let b = *ids.iter().max().unwrap();
+        Self {
+            key: (a, b, level),
+            position: [
+                solve_by_interpolation(
+                    self.value,
+                    other.value,
+                    c,
+                    self.position[0],
+                    other.position[0],
+                ),
+                solve_by_interpolation(
+                    self.value,
+                    other.value,
+                    c,
+                    self.position[1],
+                    other.position[1],
+                ),
+            ],
+            value: c,
+        }
+    }
+}
+
+/// Sutherland-Hodgman clipping of a cell polygon by `value >= c` (or
+/// `value < c` when `above` is false), with values linear along the edges.
+fn clip(polygon: &[BandPoint], level: usize, c: f32, above: bool) -> Vec<BandPoint> {
+    let inside = |p: &BandPoint| (p.value >= c) == above;
+    let mut result = Vec::new();
+    for (idx, cur) in polygon.iter().enumerate() {
+        let next = &polygon[(idx + 1) % polygon.len()];
+        if inside(cur) {
+            result.push(*cur);
+        }
+        if inside(cur) != inside(next) {
+            result.push(cur.crossing(next, level, c));
+        }
+    }
+    result
+}
+
+fn signed_area(ring: &[[f32; 2]]) -> f32 {
+    let mut area = 0.0;
+    for (idx, p) in ring.iter().enumerate() {
+        let q = ring[(idx + 1) % ring.len()];
+        area += p[0] * q[1] - q[0] * p[1];
+    }
+    area / 2.0
+}
+
+fn contains(ring: &[[f32; 2]], point: [f32; 2]) -> bool {
+    let mut inside = false;
+    for (idx, p) in ring.iter().enumerate() {
+        let q = ring[(idx + 1) % ring.len()];
+        if (p[1] > point[1]) != (q[1] > point[1])
+            && point[0] < p[0] + (point[1] - p[1]) / (q[1] - p[1]) * (q[0] - p[0])
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// A filled region: a counter-clockwise exterior ring and clockwise holes.
+pub struct Polygon {
+    pub exterior: Vec<[f32; 2]>,
+    pub holes: Vec<Vec<[f32; 2]>>,
+}
+
+/// The region where `lo <= value < hi`. The lowest and the highest bands are
+/// unbounded on one side.
+pub struct Band {
+    pub lo: f32,
+    pub hi: f32,
+    pub polygons: Vec<Polygon>,
+}
+
+pub struct Isobands {
+    levels: Vec<f32>,
+    pieces: Vec<Vec<Vec<BandPoint>>>,
+    vertices: Vec<ColoredVertex>,
+    #[cfg(feature = "gpu")]
+    program: Option<glium::Program>,
+}
+
+impl Default for Isobands {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Isobands {
+    pub fn new() -> Self {
+        Self {
+            levels: Vec::new(),
+            pieces: Vec::new(),
+            vertices: Vec::new(),
+            #[cfg(feature = "gpu")]
+            program: None,
+        }
+    }
+
+    #[cfg(feature = "gpu")]
+    fn create_program(display: &Display) -> glium::Program {
+        let vertex_shader = r#"
+        #version 140
+
+        in vec2 position;
+
+        uniform vec4 view;
+        in vec3 color;
+        out vec3 fragColor;
+
+        void main() {
+            gl_Position = vec4((position - view.xy) * view.zw, 0.0, 1.0);
+            fragColor = color;
+        }
+        "#;
+        let fragment_shader = r#"
+        #version 140
+
+        in vec3 fragColor;
+        out vec4 color;
+
+        void main() {
+            color = vec4(fragColor, 1.0);
+        }
+        "#;
+
+        glium::Program::from_source(display, vertex_shader, fragment_shader, None).unwrap()
+    }
+
+    fn get_bounds(&self, band: usize) -> (f32, f32) {
+        let lo = if band == 0 {
+            f32::NEG_INFINITY
+        } else {
+            self.levels[band - 1]
+        };
+        let hi = self.levels.get(band).copied().unwrap_or(f32::INFINITY);
+        (lo, hi)
+    }
+
+    fn get_band_color(
+        &self,
+        band: usize,
+        colormap: Colormap,
+        normalization: &Normalization,
+    ) -> [f32; 3] {
+        let (lo, hi) = self.get_bounds(band);
+        let lo = lo.max(normalization.min);
+        let hi = hi.min(normalization.max);
+        get_color(colormap, normalization, (lo + hi) / 2.0).color
+    }
+
+    /// Splits every cell of `grid` into the bands between consecutive `levels`.
+    /// Cells that are ambiguous for one of the levels are split into four
+    /// triangles around their center, like `Isolines` resolves saddles.
+    pub fn process(
+        &mut self,
+        grid: &Grid,
+        function: &dyn ScalarField,
+        levels: &[f32],
+        colormap: Colormap,
+        range: ValueRange,
+    ) {
+        self.levels = levels.to_vec();
+        self.pieces = (0..levels.len() + 1).map(|_| Vec::new()).collect();
+        let h = grid.dimensions.h as usize + 1;
+        let values: Vec<f32> = grid
+            .iterator(true)
+            .map(|p| function.get_value(p.0, p.1))
+            .collect();
+        let node = |i: i32, j: i32| {
+            let id = i as usize * h + j as usize;
+            BandPoint::node(id, grid.get_point(i, j), values[id])
+        };
+        for i in 0..grid.dimensions.w {
+            for j in 0..grid.dimensions.h {
+                let cell = [
+                    node(i, j),
+                    node(i + 1, j),
+                    node(i + 1, j + 1),
+                    node(i, j + 1),
+                ];
+                let ambiguous = levels.iter().any(|c| {
+                    let data = cell.map(|p| p.value >= *c);
+                    data[0] == data[2] && data[1] == data[3] && data[0] != data[1]
+                });
+                let polygons = if ambiguous {
+                    let (x, y) = grid.get_point(i, j);
+                    let middle_x = x + grid.get_cell_width() / 2.0;
+                    let middle_y = y + grid.get_cell_height() / 2.0;
+                    let center = BandPoint::node(
+                        values.len() + (i * grid.dimensions.h + j) as usize,
+                        (middle_x, middle_y),
+                        function.get_value(middle_x, middle_y),
+                    );
+                    (0..4)
+                        .map(|k| vec![center, cell[k], cell[(k + 1) % 4]])
+                        .collect()
+                } else {
+                    vec![cell.to_vec()]
+                };
+                for polygon in polygons {
+                    self.split(polygon);
+                }
+            }
+        }
+        let normalization = range.resolve(&values);
+        for band in 0..self.pieces.len() {
+            let color = self.get_band_color(band, colormap, &normalization);
+            for piece in &self.pieces[band] {
+                for k in 1..piece.len() - 1 {
+                    for p in [piece[0], piece[k], piece[k + 1]] {
+                        self.vertices.push(ColoredVertex {
+                            position: p.position,
+                            color,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    fn split(&mut self, polygon: Vec<BandPoint>) {
+        let mut rest = polygon;
+        for (level, c) in self.levels.iter().enumerate() {
+            if rest.is_empty() {
+                return;
+            }
+            let below = clip(&rest, level, *c, false);
+            if below.len() >= 3 {
+                self.pieces[level].push(below);
+            }
+            rest = clip(&rest, level, *c, true);
+        }
+        if rest.len() >= 3 {
+            self.pieces[self.levels.len()].push(rest);
+        }
+    }
+
+    /// Bands found by the last `process`, merged from cell pieces into polygons.
+    pub fn get_bands(&self) -> Vec<Band> {
+        (0..self.pieces.len())
+            .map(|band| {
+                let (lo, hi) = self.get_bounds(band);
+                Band {
+                    lo,
+                    hi,
+                    polygons: Self::merge(&self.pieces[band]),
+                }
+            })
+            .collect()
+    }
+
+    fn merge(pieces: &[Vec<BandPoint>]) -> Vec<Polygon> {
+        // edges shared by two pieces go in opposite directions and cancel out
+        let mut positions = HashMap::new();
+        let mut edges = HashSet::new();
+        for piece in pieces {
+            for (idx, p) in piece.iter().enumerate() {
+                let q = piece[(idx + 1) % piece.len()];
+                positions.entry(p.key).or_insert(p.position);
+                if p.key != q.key && !edges.remove(&(q.key, p.key)) {
+                    edges.insert((p.key, q.key));
+                }
+            }
+        }
+        let mut outgoing: HashMap<PointKey, Vec<PointKey>> = HashMap::new();
+        for (from, to) in &edges {
+            outgoing.entry(*from).or_default().push(*to);
+        }
+        let mut rings = Vec::new();
+        while let Some(&start) = outgoing.keys().next() {
+            let mut ring = vec![positions[&start]];
+            let mut cur = start;
+            loop {
+                let next = outgoing.get_mut(&cur).and_then(|targets| targets.pop());
+                if outgoing.get(&cur).is_some_and(|targets| targets.is_empty()) {
+                    outgoing.remove(&cur);
+                }
+                match next {
+                    Some(next) if next != start => {
+                        ring.push(positions[&next]);
+                        cur = next;
+                    }
+                    _ => break,
+                }
+            }
+            if ring.len() >= 3 {
+                rings.push(ring);
+            }
+        }
+        let (exteriors, holes): (Vec<_>, Vec<_>) =
+            rings.into_iter().partition(|ring| signed_area(ring) > 0.0);
+        let mut polygons: Vec<Polygon> = exteriors
+            .into_iter()
+            .map(|exterior| Polygon {
+                exterior,
+                holes: Vec::new(),
+            })
+            .collect();
+        for hole in holes {
+            let owner = polygons
+                .iter_mut()
+                .filter(|polygon| contains(&polygon.exterior, hole[0]))
+                .min_by(|a, b| signed_area(&a.exterior).total_cmp(&signed_area(&b.exterior)));
+            if let Some(polygon) = owner {
+                polygon.holes.push(hole);
+            }
+        }
+        polygons
+    }
+}
+
+#[cfg(feature = "gpu")]
+impl Draw for Isobands {
+    fn draw(&mut self, display: &mut Display, target: &mut Frame, viewport: &Viewport) {
+        draw_triangles(
+            target,
+            &glium::VertexBuffer::new(display, &self.vertices).unwrap(),
+            self.program
+                .get_or_insert_with(|| Self::create_program(display)),
+            viewport,
+        );
+        self.vertices.clear();
+    }
+}
+
+impl Rasterize for Isobands {
+    fn rasterize(&mut self, canvas: &mut Canvas) {
+        for triangle in self.vertices.chunks(3) {
+            canvas.fill_triangle([0, 1, 2].map(|i| (triangle[i].position, triangle[i].color)));
+        }
+        self.vertices.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Dimensions;
+    use std::f32::consts::PI;
+
+    struct Field(fn(f32, f32) -> f32);
+
+    impl ScalarField for Field {
+        fn get_value(&self, x: f32, y: f32) -> f32 {
+            (self.0)(x, y)
+        }
+
+        fn get_time(&self) -> f32 {
+            0.0
+        }
+
+        fn set_time(&mut self, _time: f32) {}
+    }
+
+    fn bands(field: fn(f32, f32) -> f32, levels: &[f32]) -> Vec<Band> {
+        let grid = Grid::new(-1.0, 1.0, -1.0, 1.0, Dimensions { w: 60, h: 60 });
+        let mut isobands = Isobands::new();
+        isobands.process(
+            &grid,
+            &Field(field),
+            levels,
+            Colormap::Viridis,
+            ValueRange::default(),
+        );
+        isobands.get_bands()
+    }
+
+    fn area(polygon: &Polygon) -> f32 {
+        signed_area(&polygon.exterior) + polygon.holes.iter().map(|h| signed_area(h)).sum::<f32>()
+    }
+
+    /// Every band is made of counter-clockwise exteriors with clockwise holes
+    /// inside them, and together the bands cover the grid.
+    fn check(bands: &[Band]) {
+        let mut total = 0.0;
+        for band in bands {
+            for polygon in &band.polygons {
+                assert!(signed_area(&polygon.exterior) > 0.0);
+                for hole in &polygon.holes {
+                    assert!(signed_area(hole) < 0.0);
+                    assert!(hole.iter().all(|p| contains(&polygon.exterior, *p)));
+                }
+                total += area(polygon);
+            }
+        }
+        assert!((total - 4.0).abs() < 1e-3, "the bands cover {}", total);
+    }
+
+    #[test]
+    fn a_bump_gives_nested_bands() {
+        let bands = bands(|x, y| 1.0 - x * x - y * y, &[0.3, 0.7]);
+        check(&bands);
+        let shape: Vec<Vec<usize>> = bands
+            .iter()
+            .map(|band| band.polygons.iter().map(|p| p.holes.len()).collect())
+            .collect();
+        // the square around the bump, the ring and the top of the bump
+        assert_eq!(shape, vec![vec![1], vec![1], vec![0]]);
+        let top = &bands[2].polygons[0];
+        assert!((area(top) - 0.3 * PI).abs() < 0.01);
+        let ring = &bands[1].polygons[0];
+        assert!((area(ring) - 0.4 * PI).abs() < 0.01);
+    }
+
+    #[test]
+    fn holes_belong_to_the_polygon_around_them() {
+        // a disk in the middle and a ring around it, in the same band
+        let bands = bands(|x, y| (2.0 * PI * (x * x + y * y).sqrt()).cos(), &[0.5]);
+        check(&bands);
+        let mut polygons: Vec<&Polygon> = bands[1].polygons.iter().collect();
+        polygons.sort_by(|a, b| area(a).total_cmp(&area(b)));
+        assert_eq!(polygons.len(), 2);
+        assert!(polygons[0].holes.is_empty());
+        assert_eq!(polygons[1].holes.len(), 1);
+        // the disk is inside the hole of the ring
+        assert!(contains(&polygons[1].holes[0], polygons[0].exterior[0]));
+    }
+}
@@ -1,12 +1,21 @@
 use std::collections::HashMap;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
 
 use crate::draw::*;
-use crate::function::PerlinNoise;
+use crate::function::ScalarField;
 use crate::grid::Grid;
-use glium::Display;
-use glium::Frame;
+use crate::raster::{Canvas, Rasterize};
+#[cfg(feature = "gpu")]
+use crate::viewport::Viewport;
+#[cfg(feature = "gpu")]
+use glium::{Display, Frame};
+
+/// Contour points closer than this fraction of a grid cell are the same point.
+const MERGE_FRACTION: f32 = 1e-3;
 
-fn solve_by_interpolation(a: f32, b: f32, c: f32, x0: f32, x1: f32) -> f32 {
+pub(crate) fn solve_by_interpolation(a: f32, b: f32, c: f32, x0: f32, x1: f32) -> f32 {
     // a * t + b (1 - t) = c
     // t = (c - b) / (a - b)
     // res = x * t + b * (1 - t)
@@ -14,33 +23,233 @@ fn solve_by_interpolation(a: f32, b: f32, c: f32, x0: f32, x1: f32) -> f32 {
     x0 * t + (1.0 - t) * x1
 }
 
+/// A contour line. A closed polyline is a ring whose last point connects back
+/// to the first one; an open one ends at the border of the grid.
+pub struct Polyline {
+    pub points: Vec<[f32; 2]>,
+    pub closed: bool,
+}
+
+/// Most levels an `Interval` may produce, so a tiny interval can't stall a
+/// frame; past it only those around the middle of the range are kept.
+const MAX_LEVELS: usize = 1000;
+
+/// Smallest `Interval` step relative to the size of its base (or 1), well
+/// above the resolution of `f32` so neighbouring levels stay apart.
+const MIN_RELATIVE_INTERVAL: f32 = 1e-5;
+
+/// How `Isolines::new` chooses the values to draw contours at.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum Levels {
+    /// `count` quantiles of the sampled values, so the contours split the
+    /// picture into equal areas.
+    Quantile { count: usize },
+    /// `count` levels evenly spaced strictly between `min` and `max`.
+    Uniform { count: usize, min: f32, max: f32 },
+    /// Every `base + k * interval` within the range of the sampled values,
+    /// which `Isolines::process` samples again every frame.
+    Interval {
+        interval: f32,
+        #[serde(default)]
+        base: f32,
+    },
+    /// Exactly these values.
+    Explicit { values: Vec<f32> },
+}
+
+impl Levels {
+    /// Levels for the sampled `values`, in increasing order.
+    pub fn compute(&self, values: &mut [f32]) -> Vec<f32> {
+        let mut levels = match self {
+            Levels::Quantile { .. } if values.is_empty() => vec![],
+            Levels::Quantile { count } => {
+                let len = values.len();
+                let cmp =
+                    |a: &f32, b: &f32| ((*a * 1000000.0) as i32).cmp(&((*b * 1000000.0) as i32));
+                let mut c_values: Vec<f32> = vec![];
+                for i in 1..count + 1 {
+                    c_values.push(*values.select_nth_unstable_by(i * len / (count + 1), cmp).1);
+                }
+                c_values
+            }
+            Levels::Uniform { count, min, max } => (1..count + 1)
+                .map(|i| min + (max - min) * i as f32 / (count + 1) as f32)
+                .collect(),
+            Levels::Interval { interval, base } => {
+                let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+                let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                // in f64, so a tiny interval can't overflow the count
+                let (base, interval) = (*base as f64, *interval as f64);
+                let mut first = ((min as f64 - base) / interval).ceil();
+                let last = ((max as f64 - base) / interval).floor();
+                let count = last - first + 1.0;
+                if !count.is_finite() || count < 1.0 {
+                    return vec![];
+                }
+                if count > MAX_LEVELS as f64 {
+                    // keep the levels around the middle of the range
+                    first = ((first + last) / 2.0).floor() - (MAX_LEVELS / 2) as f64;
+                }
+                (0..(count as usize).min(MAX_LEVELS))
+                    .map(|k| (base + (first + k as f64) * interval) as f32)
+                    .collect()
+            }
+            Levels::Explicit { values } => values.clone(),
+        };
+        levels.sort_by(f32::total_cmp);
+        levels
+    }
+
+    /// The same strategy with more levels.
+    pub fn finer(&self) -> Levels {
+        match self.clone() {
+            Levels::Quantile { count } => Levels::Quantile { count: count + 1 },
+            Levels::Uniform { count, min, max } => Levels::Uniform {
+                count: count + 1,
+                min,
+                max,
+            },
+            Levels::Interval { interval, base } => Levels::Interval {
+                // stop at the smallest interval `validate` accepts
+                interval: (interval / 2.0).max(Self::min_interval(base)).min(interval),
+                base,
+            },
+            explicit => explicit,
+        }
+    }
+
+    /// The same strategy with fewer levels.
+    pub fn coarser(&self) -> Levels {
+        match self.clone() {
+            Levels::Quantile { count } => Levels::Quantile {
+                count: count.max(1) - 1,
+            },
+            Levels::Uniform { count, min, max } => Levels::Uniform {
+                count: count.max(1) - 1,
+                min,
+                max,
+            },
+            Levels::Interval { interval, base } => Levels::Interval {
+                interval: (interval * 2.0).min(f32::MAX),
+                base,
+            },
+            explicit => explicit,
+        }
+    }
+
+    fn min_interval(base: f32) -> f32 {
+        MIN_RELATIVE_INTERVAL * base.abs().max(1.0)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Levels::Uniform { min, max, .. } if min >= max => {
+                Err(format!("uniform levels need min {} below max {}", min, max))
+            }
+            Levels::Interval { interval, .. } if !interval.is_finite() || *interval <= 0.0 => {
+                Err("the level interval must be positive".to_string())
+            }
+            Levels::Interval { interval, base } if *interval < Self::min_interval(*base) => {
+                Err(format!(
+                    "the level interval must be at least {}",
+                    Self::min_interval(*base)
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FromStr for Levels {
+    type Err = String;
+
+    /// Parses `quantile:COUNT`, `uniform:COUNT:MIN:MAX`, `interval:STEP[:BASE]`
+    /// or `list:VALUE,VALUE,...`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |value: &str| {
+            value
+                .trim()
+                .parse::<f32>()
+                .map_err(|_| format!("'{}' is not a number", value))
+        };
+        let count = |value: &str| {
+            value
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("'{}' is not a count", value))
+        };
+        let (mode, rest) = s.split_once(':').unwrap_or((s, ""));
+        let parts: Vec<&str> = rest.split(':').collect();
+        match (mode, parts.as_slice()) {
+            ("quantile", [n]) => Ok(Levels::Quantile { count: count(n)? }),
+            ("uniform", [n, min, max]) => Ok(Levels::Uniform {
+                count: count(n)?,
+                min: number(min)?,
+                max: number(max)?,
+            }),
+            ("interval", [step]) => Ok(Levels::Interval {
+                interval: number(step)?,
+                base: 0.0,
+            }),
+            ("interval", [step, base]) => Ok(Levels::Interval {
+                interval: number(step)?,
+                base: number(base)?,
+            }),
+            ("list", [values]) => Ok(Levels::Explicit {
+                values: values
+                    .split(',')
+                    .map(number)
+                    .collect::<Result<_, _>>()?,
+            }),
+            _ => Err(format!(
+                "expected quantile:COUNT, uniform:COUNT:MIN:MAX, interval:STEP[:BASE] or list:VALUE,..., got '{}'",
+                s
+            )),
+        }
+    }
+}
+
 pub struct Isolines {
+    strategy: Levels,
     c_values: Vec<f32>,
     vectors: Vec<Vertex>,
     indices: Vec<u32>,
-    program: glium::Program,
+    level_starts: Vec<usize>,
+    #[cfg(feature = "gpu")]
+    program: Option<glium::Program>,
     coords_cache: HashMap<(i32, i32, i32), usize>,
+    /// Distance within which `stitch` merges points, set by `process` from the cell size.
+    merge_distance: f32,
 }
 
 impl Isolines {
-    pub fn new(grid: &Grid, function: &PerlinNoise, display: &Display, cnt: usize) -> Self {
-        let mut values: Vec<f32> = grid
-            .iterator(false)
-            .map(|p| function.get_value(p.0, p.1, grid))
-            .collect();
-        let len = values.len();
-        let cmp = |a: &f32, b: &f32| ((*a * 1000000.0) as i32).cmp(&((*b * 1000000.0) as i32));
-        let mut c_values: Vec<f32> = vec![];
-        for i in 1..cnt + 1 {
-            c_values.push(*values.select_nth_unstable_by(i * len / (cnt + 1), cmp).1);
+    pub fn new(grid: &Grid, function: &dyn ScalarField, strategy: &Levels) -> Self {
+        let mut values = Self::sample(grid, function);
+        Self {
+            strategy: strategy.clone(),
+            c_values: strategy.compute(&mut values),
+            vectors: Vec::new(),
+            indices: Vec::new(),
+            level_starts: Vec::new(),
+            #[cfg(feature = "gpu")]
+            program: None,
+            coords_cache: HashMap::new(),
+            merge_distance: MERGE_FRACTION,
         }
+    }
+
+    #[cfg(feature = "gpu")]
+    fn create_program(display: &Display) -> glium::Program {
         let vertex_shader = r#"
         #version 140
         
         in vec2 position;
         
+        uniform vec4 view;
+        
         void main() {
-            gl_Position = vec4(position, 0.0, 1.0);
+            gl_Position = vec4((position - view.xy) * view.zw, 0.0, 1.0);
         }
         "#;
         let fragment_shader = r#"
@@ -53,33 +262,34 @@ impl Isolines {
         }
         "#;
 
-        let program =
-            glium::Program::from_source(display, vertex_shader, fragment_shader, None).unwrap();
-        Self {
-            c_values,
-            vectors: Vec::new(),
-            indices: Vec::new(),
-            program,
-            coords_cache: HashMap::new(),
-        }
+        glium::Program::from_source(display, vertex_shader, fragment_shader, None).unwrap()
     }
 
-    pub fn increase_precision(&mut self, grid: &Grid, function: &PerlinNoise, display: &Display) {
-        *self = Self::new(grid, function, display, self.c_values.len() + 1);
+    fn sample(grid: &Grid, function: &dyn ScalarField) -> Vec<f32> {
+        grid.iterator(false)
+            .map(|p| function.get_value(p.0, p.1))
+            .collect()
     }
 
-    pub fn decrease_precision(&mut self, grid: &Grid, function: &PerlinNoise, display: &Display) {
-        *self = Self::new(grid, function, display, self.c_values.len().max(1) - 1);
+    pub fn increase_precision(&mut self, grid: &Grid, function: &dyn ScalarField) {
+        *self = Self::new(grid, function, &self.strategy.finer());
+    }
+
+    pub fn decrease_precision(&mut self, grid: &Grid, function: &dyn ScalarField) {
+        *self = Self::new(grid, function, &self.strategy.coarser());
     }
 
     fn add_point(&mut self, x: f32, y: f32, x_idx: i32, y_idx: i32, tp: i32) -> usize {
-        *self
-            .coords_cache
-            .entry((x_idx, y_idx, tp))
-            .or_insert_with(|| {
-                self.vectors.push(Vertex { position: [x, y] });
-                self.vectors.len() - 1
-            })
+        // top and right edges of a cell are bottom and left edges of its neighbours
+        let key = match tp {
+            5 => (x_idx, y_idx + 1, 1),
+            3 => (x_idx + 1, y_idx, 7),
+            _ => (x_idx, y_idx, tp),
+        };
+        *self.coords_cache.entry(key).or_insert_with(|| {
+            self.vectors.push(Vertex { position: [x, y] });
+            self.vectors.len() - 1
+        })
     }
 
     fn draw_vector(&mut self, x_idx: i32, y_idx: i32, p0: (f32, f32, i32), p1: (f32, f32, i32)) {
@@ -91,21 +301,28 @@ impl Isolines {
         self.indices.push(idx);
     }
 
-    pub fn process(&mut self, grid: &Grid, function: &PerlinNoise) {
+    /// Contours of `function` at the levels. `Interval` levels follow the
+    /// range of the current values, the others stay as `new` computed them.
+    pub fn process(&mut self, grid: &Grid, function: &dyn ScalarField) {
+        if let Levels::Interval { .. } = self.strategy {
+            self.c_values = self.strategy.compute(&mut Self::sample(grid, function));
+        }
+        self.merge_distance = MERGE_FRACTION * grid.get_cell_width().min(grid.get_cell_height());
         for c in self.c_values.clone() {
             self.coords_cache.clear();
+            self.level_starts.push(self.indices.len());
             for (x, y) in grid.iterator(false) {
                 let nx = x + grid.get_cell_width();
                 let ny = y + grid.get_cell_height();
                 let values: Vec<f32> = vec![(x, y), (nx, y), (x, ny), (nx, ny)]
                     .into_iter()
-                    .map(|p| function.get_value(p.0, p.1, grid))
+                    .map(|p| function.get_value(p.0, p.1))
                     .collect();
                 let data: Vec<bool> = values.iter().map(|p| *p >= c).collect();
                 if data[0] ^ data[1] && data[2] ^ data[3] && data[0] ^ data[2] {
                     let middle_x = x + grid.get_cell_width() / 2.0;
                     let middle_y = y + grid.get_cell_height() / 2.0;
-                    let center = function.get_value(middle_x, middle_y, grid) >= c;
+                    let center = function.get_value(middle_x, middle_y) >= c;
                     if center ^ data[0] {
                         let mx = solve_by_interpolation(values[0], values[1], c, x, nx);
                         let my = solve_by_interpolation(values[0], values[2], c, y, ny);
@@ -207,13 +424,103 @@ impl Isolines {
         }
     }
 
-    pub fn get_precision(&self) -> usize {
-        self.c_values.len()
+    pub fn get_strategy(&self) -> &Levels {
+        &self.strategy
+    }
+
+    pub fn get_levels(&self) -> &[f32] {
+        &self.c_values
+    }
+
+    fn get_level_indices(&self, level: usize) -> &[u32] {
+        let end = self
+            .level_starts
+            .get(level + 1)
+            .copied()
+            .unwrap_or(self.indices.len());
+        &self.indices[self.level_starts[level]..end]
+    }
+
+    /// Contours found by the last `process`, grouped by their level.
+    pub fn get_polylines(&self) -> Vec<(f32, Vec<Polyline>)> {
+        self.c_values
+            .iter()
+            .zip(0..self.level_starts.len())
+            .map(|(c, level)| (*c, self.stitch(self.get_level_indices(level))))
+            .collect()
+    }
+
+    fn stitch(&self, indices: &[u32]) -> Vec<Polyline> {
+        // a contour passing exactly through a grid node is found on several
+        // edges of that node, so points are merged by position too
+        let mut by_position: HashMap<(i64, i64), u32> = HashMap::new();
+        let distance = self.merge_distance;
+        let canonical: Vec<u32> = indices
+            .iter()
+            .map(|idx| {
+                let [x, y] = self.vectors[*idx as usize].position;
+                *by_position
+                    .entry(((x / distance).round() as i64, (y / distance).round() as i64))
+                    .or_insert(*idx)
+            })
+            .collect();
+        let segments: Vec<&[u32]> = canonical
+            .chunks(2)
+            .filter(|segment| segment[0] != segment[1])
+            .collect();
+        let indices: Vec<u32> = segments.concat();
+        let mut adjacent: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (idx, segment) in segments.iter().enumerate() {
+            adjacent.entry(segment[0]).or_default().push(idx);
+            adjacent.entry(segment[1]).or_default().push(idx);
+        }
+        let mut used = vec![false; segments.len()];
+        let mut polylines = Vec::new();
+        // open contours start at a border point, which belongs to a single segment
+        let ends: Vec<u32> = indices
+            .iter()
+            .copied()
+            .filter(|point| adjacent[point].len() == 1)
+            .collect();
+        for start in ends.into_iter().chain(indices.iter().copied()) {
+            let Some(&first) = adjacent[&start].iter().find(|s| !used[**s]) else {
+                continue;
+            };
+            let mut points = vec![start];
+            let mut segment = first;
+            loop {
+                used[segment] = true;
+                let last = *points.last().unwrap();
+                let next = if segments[segment][0] == last {
+                    segments[segment][1]
+                } else {
+                    segments[segment][0]
+                };
+                points.push(next);
+                match adjacent[&next].iter().find(|s| !used[**s]) {
+                    Some(&s) => segment = s,
+                    None => break,
+                }
+            }
+            let closed = points.len() > 2 && points.first() == points.last();
+            if closed {
+                points.pop();
+            }
+            polylines.push(Polyline {
+                points: points
+                    .into_iter()
+                    .map(|idx| self.vectors[idx as usize].position)
+                    .collect(),
+                closed,
+            });
+        }
+        polylines
     }
 }
 
+#[cfg(feature = "gpu")]
 impl Draw for Isolines {
-    fn draw(&mut self, display: &mut Display, target: &mut Frame) {
+    fn draw(&mut self, display: &mut Display, target: &mut Frame, viewport: &Viewport) {
         draw_vectors(
             target,
             &glium::VertexBuffer::new(display, &self.vectors).unwrap(),
@@ -223,9 +530,223 @@ impl Draw for Isolines {
                 &self.indices,
             )
             .unwrap(),
-            &self.program,
+            self.program
+                .get_or_insert_with(|| Self::create_program(display)),
+            viewport,
         );
         self.vectors.clear();
         self.indices.clear();
+        self.level_starts.clear();
+    }
+}
+
+impl Rasterize for Isolines {
+    fn rasterize(&mut self, canvas: &mut Canvas) {
+        for segment in self.indices.chunks(2) {
+            canvas.draw_line(
+                self.vectors[segment[0] as usize].position,
+                self.vectors[segment[1] as usize].position,
+                [0.0, 0.0, 0.0],
+            );
+        }
+        self.vectors.clear();
+        self.indices.clear();
+        self.level_starts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Dimensions;
+
+    struct Field(fn(f32, f32) -> f32);
+
+    impl ScalarField for Field {
+        fn get_value(&self, x: f32, y: f32) -> f32 {
+            (self.0)(x, y)
+        }
+
+        fn get_time(&self) -> f32 {
+            0.0
+        }
+
+        fn set_time(&mut self, _time: f32) {}
+    }
+
+    fn contours(field: fn(f32, f32) -> f32, extent: [f32; 4], level: f32) -> Vec<Polyline> {
+        let [x0, x1, y0, y1] = extent;
+        let grid = Grid::new(x0, x1, y0, y1, Dimensions { w: 21, h: 21 });
+        let field = Field(field);
+        let levels = Levels::Explicit {
+            values: vec![level],
+        };
+        let mut isolines = Isolines::new(&grid, &field, &levels);
+        isolines.process(&grid, &field);
+        isolines.get_polylines().remove(0).1
+    }
+
+    #[test]
+    fn a_line_across_the_grid_is_one_open_polyline() {
+        let polylines = contours(|x, y| x - 0.3 * y, [-1.0, 1.0, -1.0, 1.0], 0.0);
+        assert_eq!(polylines.len(), 1);
+        assert!(!polylines[0].closed);
+        assert!(polylines[0].points.len() > 20);
+    }
+
+    #[test]
+    fn a_circle_is_one_closed_polyline() {
+        let polylines = contours(|x, y| x * x + y * y, [-1.0, 1.0, -1.0, 1.0], 0.3);
+        assert_eq!(polylines.len(), 1);
+        assert!(polylines[0].closed);
+    }
+
+    #[test]
+    fn a_tiny_circle_is_still_one_closed_polyline() {
+        let polylines = contours(
+            |x, y| (x - 0.30005).powi(2) + (y - 0.30005).powi(2),
+            [0.3, 0.3001, 0.3, 0.3001],
+            3e-5 * 3e-5,
+        );
+        assert_eq!(polylines.len(), 1);
+        assert!(polylines[0].closed);
+        assert!(polylines[0].points.len() > 20);
+    }
+
+    #[test]
+    fn a_saddle_splits_into_two_branches() {
+        // the middle cell has the saddle point at its center
+        for level in [0.001, -0.001] {
+            let polylines = contours(|x, y| x * y, [-1.0, 1.0, -1.0, 1.0], level);
+            assert_eq!(polylines.len(), 2);
+            assert!(polylines.iter().all(|polyline| !polyline.closed));
+        }
+    }
+
+    #[test]
+    fn levels_parse_every_mode() {
+        let parse = |s: &str| s.parse::<Levels>().unwrap();
+        assert_eq!(parse("quantile:5"), Levels::Quantile { count: 5 });
+        assert_eq!(
+            parse("uniform:3:-1:1"),
+            Levels::Uniform {
+                count: 3,
+                min: -1.0,
+                max: 1.0
+            }
+        );
+        assert_eq!(
+            parse("interval:0.25"),
+            Levels::Interval {
+                interval: 0.25,
+                base: 0.0
+            }
+        );
+        assert_eq!(
+            parse("interval:0.25:0.1"),
+            Levels::Interval {
+                interval: 0.25,
+                base: 0.1
+            }
+        );
+        assert_eq!(
+            parse("list:0.5, -0.5,1"),
+            Levels::Explicit {
+                values: vec![0.5, -0.5, 1.0]
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_levels_are_rejected() {
+        for s in [
+            "",
+            "quantile",
+            "quantile:x",
+            "quantile:3:4",
+            "uniform:3:1",
+            "interval:a",
+            "interval:1:2:3",
+            "list:1,b",
+            "bogus:3",
+        ] {
+            assert!(s.parse::<Levels>().is_err(), "'{}' parsed", s);
+        }
+    }
+
+    #[test]
+    fn levels_are_computed_in_increasing_order() {
+        let mut values: Vec<f32> = (0..100).map(|i| i as f32 / 100.0).collect();
+        assert_eq!(
+            Levels::Quantile { count: 3 }.compute(&mut values),
+            vec![0.25, 0.5, 0.75]
+        );
+        assert_eq!(
+            Levels::Uniform {
+                count: 3,
+                min: 0.0,
+                max: 1.0
+            }
+            .compute(&mut values),
+            vec![0.25, 0.5, 0.75]
+        );
+        assert_eq!(
+            Levels::Interval {
+                interval: 0.25,
+                base: 0.1
+            }
+            .compute(&mut values),
+            vec![0.1, 0.35, 0.6, 0.85]
+        );
+        assert_eq!(
+            Levels::Explicit {
+                values: vec![0.5, -0.5, 1.0]
+            }
+            .compute(&mut values),
+            vec![-0.5, 0.5, 1.0]
+        );
+        assert!(Levels::Quantile { count: 3 }.compute(&mut []).is_empty());
+        assert!(Levels::Interval {
+            interval: 0.25,
+            base: 0.0
+        }
+        .compute(&mut [])
+        .is_empty());
+    }
+
+    #[test]
+    fn a_tiny_interval_is_capped_around_the_middle() {
+        let mut values = vec![-1.0, 1.0];
+        let levels = Levels::Interval {
+            interval: 1e-5,
+            base: 0.0,
+        }
+        .compute(&mut values);
+        assert_eq!(levels.len(), MAX_LEVELS);
+        assert!(levels.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(levels[0] < 0.0 && levels[MAX_LEVELS - 1] > 0.0);
+        let levels = Levels::Interval {
+            interval: 1e-20,
+            base: 0.0,
+        }
+        .compute(&mut values);
+        assert_eq!(levels.len(), MAX_LEVELS);
+    }
+
+    #[test]
+    fn intervals_stop_short_of_the_resolution_of_f32() {
+        let tiny = Levels::Interval {
+            interval: 1e-20,
+            base: 0.0,
+        };
+        assert!(tiny.validate().is_err());
+        let mut levels = Levels::Interval {
+            interval: 0.1,
+            base: 0.0,
+        };
+        for _ in 0..100 {
+            levels = levels.finer();
+        }
+        assert!(levels.validate().is_ok());
     }
 }
@@ -0,0 +1,20 @@
+//! Perlin noise fields, marching squares isolines and isobands, and their
+//! rendering to images, SVG and GeoJSON. Drawing with glium is behind the
+//! `gpu` feature, so the rest can be used without a window.
+
+#![deny(clippy::correctness)]
+#![deny(clippy::perf)]
+
+pub mod background;
+pub mod colormap;
+pub mod draw;
+pub mod function;
+pub mod geojson;
+pub mod grid;
+pub mod isoband;
+pub mod isoline;
+pub mod overlay;
+pub mod range;
+pub mod raster;
+pub mod svg;
+pub mod viewport;
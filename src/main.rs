@@ -1,64 +1,211 @@
 #![deny(clippy::correctness)]
 #![deny(clippy::perf)]
 
-pub mod background;
-pub mod draw;
-pub mod function;
-pub mod grid;
-pub mod isoline;
-use crate::isoline::Isolines;
-use draw::Draw;
-use function::PerlinNoise;
-use glium::glutin::event::{ElementState, VirtualKeyCode, WindowEvent};
+mod cli;
+mod scene;
+
+use cli::Args;
+use glium::glutin::event::{
+    ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+};
 use glium::Surface;
-use grid::Dimensions;
+use hw1_function_heatmap::draw::Draw;
+use hw1_function_heatmap::function::{FractalNoise, ScalarField};
+use hw1_function_heatmap::grid::Dimensions;
+use hw1_function_heatmap::isoband::Isobands;
+use hw1_function_heatmap::isoline::Isolines;
+use hw1_function_heatmap::overlay::LatticeOverlay;
+use hw1_function_heatmap::raster::{Canvas, GifWriter, Rasterize};
+use hw1_function_heatmap::viewport::Viewport;
+use hw1_function_heatmap::{background, geojson, svg};
+use std::path::{Path, PathBuf};
 
-/// https://glium-doc.github.io/#/tuto-01-getting-started
+/// How far LEFT/RIGHT move the animation, in seconds.
+const SEEK_STEP: f32 = 1.0;
+
+/// `path` with the frame number before its extension when rendering several frames.
+fn frame_path(path: &str, frame: usize, frames: usize) -> PathBuf {
+    let path = Path::new(path);
+    if frames == 1 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}_{:04}.{}", stem, frame, extension.to_string_lossy()),
+        None => format!("{}_{:04}", stem, frame),
+    };
+    path.with_file_name(name)
+}
+
+/// The window title, naming what N, W, M and C switch between.
+fn window_title(args: &Args) -> String {
+    format!(
+        "Perlin Noise function ({}, {}, {}, {})",
+        args.noise, args.worley, args.metric, args.colormap
+    )
+}
 
+fn render_headless(function: &mut FractalNoise, args: &Args) {
+    let colormap = args.colormap;
+    let range = args.get_range();
+    let viewport = Viewport::fit(args.extent, args.window);
+    let mut background = background::Background::new(viewport.get_grid(args.grid));
+    let mut isolines = Isolines::new(&background.grid, function, &args.get_levels());
+    let mut isobands = Isobands::new();
+    let mut overlay = LatticeOverlay::new();
+    let (width, height) = (args.window.w as u32, args.window.h as u32);
+    let mut gif = args
+        .gif
+        .as_ref()
+        .map(|path| GifWriter::create(path, width, height, args.fps).unwrap());
+    for frame in 0..args.frames {
+        // from the start time every frame, so that rounding errors don't add up
+        function.set_time(args.time + frame as f32 * args.speed / args.fps);
+        if args.bands {
+            // the isolines first, they may pick new levels for the bands
+            isolines.process(&background.grid, function);
+            isobands.process(
+                &background.grid,
+                function,
+                isolines.get_levels(),
+                colormap,
+                range,
+            );
+        } else {
+            background.process(function, colormap, range, args.get_relief(), &mut isolines);
+        }
+        if let Some(path) = &args.svg {
+            let path = frame_path(path, frame, args.frames);
+            svg::write_svg(path, &isolines, &background.grid, width, height).unwrap();
+        }
+        if let Some(path) = &args.geojson {
+            let bands = if args.bands {
+                isobands.get_bands()
+            } else {
+                Vec::new()
+            };
+            let path = frame_path(path, frame, args.frames);
+            geojson::write_geojson(path, &isolines, &bands).unwrap();
+        }
+        let mut canvas = Canvas::new(width, height, args.clear_color, viewport);
+        if args.bands {
+            isobands.rasterize(&mut canvas);
+        } else {
+            background.rasterize(&mut canvas);
+        }
+        isolines.rasterize(&mut canvas);
+        if let Some(lattice) = function.get_gradient_lattice() {
+            overlay.process(lattice, &viewport, args.arrows, args.lattice_lines);
+        }
+        overlay.rasterize(&mut canvas);
+        if let Some(path) = &args.png {
+            canvas
+                .save_png(frame_path(path, frame, args.frames))
+                .unwrap();
+        }
+        if let Some(gif) = &mut gif {
+            gif.add_frame(&canvas).unwrap();
+        }
+    }
+}
+
+/// https://glium-doc.github.io/#/tuto-01-getting-started
 fn main() {
+    let mut args = Args::parse_and_validate();
+    let mut function = FractalNoise::with_kind(
+        args.noise,
+        args.lattice,
+        args.octaves,
+        args.lacunarity,
+        args.gain,
+        args.get_seed(),
+    );
+    function.set_tileable(args.tileable);
+    function.set_cellular(args.worley, args.metric);
+    function.set_time(args.time);
+    if args.is_headless() {
+        render_headless(&mut function, &args);
+        return;
+    }
+    let range = args.get_range();
+    let frame_interval = args.get_frame_interval();
+    let [red, green, blue] = args.clear_color;
     // 1. The **winit::EventsLoop** for handling events.
     let events_loop = glium::glutin::event_loop::EventLoop::new();
     // 2. Parameters for building the Window.
     let wb = glium::glutin::window::WindowBuilder::new()
-        .with_inner_size(glium::glutin::dpi::LogicalSize::new(1000.0, 1000.0))
-        .with_title("Perlin Noise function");
+        .with_inner_size(glium::glutin::dpi::PhysicalSize::new(
+            args.window.w as u32,
+            args.window.h as u32,
+        ))
+        .with_title(window_title(&args));
     // 3. Parameters for building the OpenGL context.
     let cb = glium::glutin::ContextBuilder::new();
     // 4. Build the Display with the given window and OpenGL context parameters and register the
     //    window with the events_loop.
     let mut display = glium::Display::new(wb, cb, &events_loop).unwrap();
-    let mut background = background::Background::new(
-        grid::Grid::new(
-            -1.0,
-            1.0,
-            -1.0,
-            1.0,
-            grid::Dimensions { w: 100, h: 100 },
-            grid::Dimensions { w: 1000, h: 1000 },
-        ),
-        &display,
-    );
-    let mut function = PerlinNoise::new(background::GRID.dimensions);
-    let mut isolines = Isolines::new(&background.grid, &function, &display, 5);
+    let mut window = args.window;
+    let mut viewport = Viewport::fit(args.extent, window);
+    let mut background = background::Background::new(viewport.get_grid(args.grid));
+    let mut isolines = Isolines::new(&background.grid, &function, &args.get_levels());
+    let mut isobands = Isobands::new();
+    let mut overlay = LatticeOverlay::new();
     let mut last_time = std::time::Instant::now();
     let mut paused: bool = false;
+    let mut cursor = (0.0, 0.0);
+    let mut dragging = false;
+    let mut view_changed = false;
     events_loop.run(move |ev, _, control_flow| {
         let cur_time = std::time::Instant::now();
 
-        if (cur_time - last_time).as_millis() > 100 && !paused {
+        if view_changed {
+            background = background::Background::new(viewport.get_grid(background.grid.dimensions));
+            let levels = isolines.get_strategy().clone();
+            isolines = Isolines::new(&background.grid, &function, &levels);
+        }
+        let animate = cur_time - last_time > frame_interval && !paused;
+        if animate || view_changed {
             // redraw
 
-            let next_frame_time = std::time::Instant::now() + std::time::Duration::from_millis(100);
+            let next_frame_time = std::time::Instant::now() + frame_interval;
             *control_flow = glium::glutin::event_loop::ControlFlow::WaitUntil(next_frame_time);
 
             let mut target = display.draw();
-            target.clear_color(0.0, 0.2, 0.05, 1.0);
-            function.update();
-            background.process(&function, &mut isolines);
-            background.draw(&mut display, &mut target);
-            isolines.draw(&mut display, &mut target);
+            target.clear_color(red, green, blue, 1.0);
+            if animate {
+                let dt = (cur_time - last_time).as_secs_f32() * args.speed;
+                args.time += dt;
+                function.set_time(args.time);
+                last_time = std::time::Instant::now();
+            }
+            view_changed = false;
+            if args.bands {
+                // the isolines first, they may pick new levels for the bands
+                isolines.process(&background.grid, &function);
+                isobands.process(
+                    &background.grid,
+                    &function,
+                    isolines.get_levels(),
+                    args.colormap,
+                    range,
+                );
+                isobands.draw(&mut display, &mut target, &viewport);
+            } else {
+                background.process(
+                    &function,
+                    args.colormap,
+                    range,
+                    args.get_relief(),
+                    &mut isolines,
+                );
+                background.draw(&mut display, &mut target, &viewport);
+            }
+            isolines.draw(&mut display, &mut target, &viewport);
+            if let Some(lattice) = function.get_gradient_lattice() {
+                overlay.process(lattice, &viewport, args.arrows, args.lattice_lines);
+            }
+            overlay.draw(&mut display, &mut target, &viewport);
             target.finish().unwrap();
-            last_time = std::time::Instant::now();
         }
 
         if let glium::glutin::event::Event::WindowEvent { event, .. } = ev {
@@ -70,43 +217,126 @@ fn main() {
                     if let ElementState::Pressed = input.state {
                         match input.virtual_keycode {
                             Some(VirtualKeyCode::Plus) => {
-                                isolines.increase_precision(&background.grid, &function, &display);
+                                isolines.increase_precision(&background.grid, &function);
                             }
                             Some(VirtualKeyCode::Minus) => {
-                                isolines.decrease_precision(&background.grid, &function, &display);
+                                isolines.decrease_precision(&background.grid, &function);
                             }
                             Some(VirtualKeyCode::Up) => {
                                 background.grid.dimensions.w += 5;
                                 background.grid.dimensions.h += 5;
-                                background = background::Background::new(background.grid, &display);
+                                background = background::Background::new(background.grid);
                             }
-                            Some(VirtualKeyCode::Down) => {
+                            Some(VirtualKeyCode::Down)
+                                if background.grid.dimensions.w > 5
+                                    && background.grid.dimensions.h > 5 =>
+                            {
                                 background.grid.dimensions.w -= 5;
                                 background.grid.dimensions.h -= 5;
-                                background = background::Background::new(background.grid, &display);
+                                background = background::Background::new(background.grid);
+                            }
+                            Some(VirtualKeyCode::Left) => {
+                                args.time -= SEEK_STEP;
+                                function.set_time(args.time);
+                                view_changed = true;
+                            }
+                            Some(VirtualKeyCode::Right) => {
+                                args.time += SEEK_STEP;
+                                function.set_time(args.time);
+                                view_changed = true;
                             }
                             Some(VirtualKeyCode::Space) => {
                                 paused ^= true;
                             }
+                            Some(VirtualKeyCode::B) => {
+                                args.bands ^= true;
+                            }
+                            Some(VirtualKeyCode::R) => {
+                                args.relief ^= true;
+                            }
+                            Some(VirtualKeyCode::V) => {
+                                args.arrows ^= true;
+                            }
+                            Some(VirtualKeyCode::L) => {
+                                args.lattice_lines ^= true;
+                            }
+                            Some(VirtualKeyCode::N) => {
+                                args.noise = args.noise.next();
+                                function = FractalNoise::with_kind(
+                                    args.noise,
+                                    args.lattice,
+                                    args.octaves,
+                                    args.lacunarity,
+                                    args.gain,
+                                    args.get_seed(),
+                                );
+                                function.set_tileable(args.tileable);
+                                function.set_cellular(args.worley, args.metric);
+                                function.set_time(args.time);
+                                // the levels were picked for the values of the old noise
+                                view_changed = true;
+                                display.gl_window().window().set_title(&window_title(&args));
+                            }
+                            Some(VirtualKeyCode::W) => {
+                                args.worley = args.worley.next();
+                                function.set_cellular(args.worley, args.metric);
+                                view_changed = true;
+                                display.gl_window().window().set_title(&window_title(&args));
+                            }
+                            Some(VirtualKeyCode::M) => {
+                                args.metric = args.metric.next();
+                                function.set_cellular(args.worley, args.metric);
+                                view_changed = true;
+                                display.gl_window().window().set_title(&window_title(&args));
+                            }
+                            Some(VirtualKeyCode::C) => {
+                                args.colormap = args.colormap.next();
+                                display.gl_window().window().set_title(&window_title(&args));
+                            }
+                            Some(VirtualKeyCode::S) => {
+                                args.extent = viewport.get_extent();
+                                args.grid = background.grid.dimensions;
+                                args.levels = Some(isolines.get_strategy().clone());
+                                match args.to_scene().save(&args.scene) {
+                                    Ok(()) => println!("Saved the scene to {}", args.scene),
+                                    Err(e) => eprintln!("Can't save {}: {}", args.scene, e),
+                                }
+                            }
                             _ => (),
                         }
                     }
                 }
-                WindowEvent::Resized(size) => {
+                WindowEvent::Resized(size) if size.width > 0 && size.height > 0 => {
                     let new_dim = Dimensions {
                         w: size.width as i32,
                         h: size.height as i32,
                     };
-                    background = background::Background::new(
-                        grid::Grid::new(-1.0, 1.0, -1.0, 1.0, background.grid.dimensions, new_dim),
-                        &display,
-                    );
-                    isolines = Isolines::new(
-                        &background.grid,
-                        &function,
-                        &display,
-                        isolines.get_precision(),
-                    );
+                    viewport = viewport.resize(window, new_dim);
+                    window = new_dim;
+                    view_changed = true;
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    if dragging {
+                        viewport.pan((position.x - cursor.0, position.y - cursor.1), window);
+                        view_changed = true;
+                    }
+                    cursor = (position.x, position.y);
+                }
+                WindowEvent::MouseInput {
+                    state,
+                    button: MouseButton::Left,
+                    ..
+                } => {
+                    dragging = state == ElementState::Pressed;
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    let lines = match delta {
+                        MouseScrollDelta::LineDelta(_, y) => y,
+                        MouseScrollDelta::PixelDelta(position) => position.y as f32 / 50.0,
+                    };
+                    // scrolling up zooms in around the point under the cursor
+                    viewport.zoom(0.9_f32.powf(lines), viewport.to_world(cursor, window));
+                    view_changed = true;
                 }
                 _ => (),
             }
@@ -0,0 +1,155 @@
+use crate::draw::*;
+use crate::function::GradientLattice;
+use crate::raster::{Canvas, Rasterize};
+use crate::viewport::Viewport;
+#[cfg(feature = "gpu")]
+use glium::{Display, Frame};
+
+const COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+/// Most lattice nodes in view to draw arrows for; past it they'd be a few
+/// pixels apart at best and only slow down every frame.
+const MAX_ARROWS: i32 = 20_000;
+/// Most lattice lines in view to draw.
+const MAX_LINES: i32 = 2_000;
+
+/// Arrows along the vectors at the nodes of a `GradientLattice` and the lines
+/// of the lattice.
+pub struct LatticeOverlay {
+    vectors: Vec<Vertex>,
+    indices: Vec<u32>,
+    #[cfg(feature = "gpu")]
+    program: Option<glium::Program>,
+}
+
+impl Default for LatticeOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatticeOverlay {
+    pub fn new() -> Self {
+        Self {
+            vectors: Vec::new(),
+            indices: Vec::new(),
+            #[cfg(feature = "gpu")]
+            program: None,
+        }
+    }
+
+    #[cfg(feature = "gpu")]
+    fn create_program(display: &Display) -> glium::Program {
+        let vertex_shader = r#"
+        #version 140
+
+        in vec2 position;
+        uniform vec4 view;
+
+        void main() {
+            gl_Position = vec4((position - view.xy) * view.zw, 0.0, 1.0);
+        }
+        "#;
+        let fragment_shader = r#"
+        #version 140
+
+        out vec4 color;
+
+        void main() {
+            color = vec4(1.0, 1.0, 1.0, 1.0);
+        }
+        "#;
+
+        glium::Program::from_source(display, vertex_shader, fragment_shader, None).unwrap()
+    }
+
+    fn add_line(&mut self, from: (f32, f32), to: (f32, f32)) {
+        for point in [from, to] {
+            self.indices.push(self.vectors.len() as u32);
+            self.vectors.push(Vertex {
+                position: [point.0, point.1],
+            });
+        }
+    }
+
+    /// Builds the arrows and the lattice lines of `noise` within `viewport`,
+    /// skipping each once the lattice is too dense to show.
+    pub fn process(
+        &mut self,
+        noise: &dyn GradientLattice,
+        viewport: &Viewport,
+        arrows: bool,
+        lines: bool,
+    ) {
+        let lattice = noise.get_lattice();
+        let (width, height) = (lattice.get_cell_width(), lattice.get_cell_height());
+        let first = lattice.get_point_rev(viewport.x0, viewport.y0);
+        let last = lattice.get_point_rev(viewport.x1, viewport.y1);
+        let (columns, rows) = (last.0 - first.0 + 3, last.1 - first.1 + 3);
+        if lines && columns + rows <= MAX_LINES {
+            for i in first.0 - 1..=last.0 + 1 {
+                let (x, _) = lattice.get_point(i, 0);
+                if (viewport.x0..=viewport.x1).contains(&x) {
+                    self.add_line((x, viewport.y0), (x, viewport.y1));
+                }
+            }
+            for j in first.1 - 1..=last.1 + 1 {
+                let (_, y) = lattice.get_point(0, j);
+                if (viewport.y0..=viewport.y1).contains(&y) {
+                    self.add_line((viewport.x0, y), (viewport.x1, y));
+                }
+            }
+        }
+        if !arrows || columns.saturating_mul(rows) > MAX_ARROWS {
+            return;
+        }
+        for (point, direction) in noise.get_nodes(viewport.get_extent()) {
+            // the vectors are in lattice cell units, an arrow is 40% of a cell long
+            let arrow = (0.4 * direction.0 * width, 0.4 * direction.1 * height);
+            let tip = (point.0 + arrow.0, point.1 + arrow.1);
+            self.add_line(point, tip);
+            for angle in [2.6_f32, -2.6] {
+                let (sin, cos) = angle.sin_cos();
+                let head = (
+                    0.3 * (arrow.0 * cos - arrow.1 * sin),
+                    0.3 * (arrow.0 * sin + arrow.1 * cos),
+                );
+                self.add_line(tip, (tip.0 + head.0, tip.1 + head.1));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gpu")]
+impl Draw for LatticeOverlay {
+    fn draw(&mut self, display: &mut Display, target: &mut Frame, viewport: &Viewport) {
+        draw_vectors(
+            target,
+            &glium::VertexBuffer::new(display, &self.vectors).unwrap(),
+            &glium::IndexBuffer::new(
+                display,
+                glium::index::PrimitiveType::LinesList,
+                &self.indices,
+            )
+            .unwrap(),
+            self.program
+                .get_or_insert_with(|| Self::create_program(display)),
+            viewport,
+        );
+        self.vectors.clear();
+        self.indices.clear();
+    }
+}
+
+impl Rasterize for LatticeOverlay {
+    fn rasterize(&mut self, canvas: &mut Canvas) {
+        for segment in self.indices.chunks(2) {
+            canvas.draw_line(
+                self.vectors[segment[0] as usize].position,
+                self.vectors[segment[1] as usize].position,
+                COLOR,
+            );
+        }
+        self.vectors.clear();
+        self.indices.clear();
+    }
+}
@@ -0,0 +1,49 @@
+/// How the values of a frame are mapped to `[0, 1]` before colormapping.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueRange {
+    /// The range of the current frame, ignoring `clip` percent of the values
+    /// on each side so that a few extremes don't wash out the rest.
+    Auto { clip: f32 },
+    /// The same range for every frame.
+    Fixed { min: f32, max: f32 },
+}
+
+impl Default for ValueRange {
+    fn default() -> Self {
+        ValueRange::Auto { clip: 0.0 }
+    }
+}
+
+impl ValueRange {
+    pub fn resolve(&self, values: &[f32]) -> Normalization {
+        match *self {
+            ValueRange::Fixed { min, max } => Normalization { min, max },
+            ValueRange::Auto { .. } if values.is_empty() => Normalization { min: 0.0, max: 1.0 },
+            ValueRange::Auto { clip } => {
+                let mut sorted = values.to_vec();
+                sorted.sort_by(f32::total_cmp);
+                let last = sorted.len() - 1;
+                let skip = ((clip.clamp(0.0, 50.0) / 100.0) * last as f32).round() as usize;
+                Normalization {
+                    min: sorted[skip],
+                    max: sorted[last - skip],
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Normalization {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Normalization {
+    pub fn normalize(&self, value: f32) -> f32 {
+        if self.max <= self.min {
+            return 0.5;
+        }
+        ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+    }
+}
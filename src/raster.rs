@@ -0,0 +1,148 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::viewport::Viewport;
+
+/// CPU counterpart of `Draw`: renders into a `Canvas` instead of a glium `Frame`.
+pub trait Rasterize {
+    fn rasterize(&mut self, canvas: &mut Canvas);
+}
+
+/// An RGB image of `viewport`, addressed in world coordinates like the shaders.
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    viewport: Viewport,
+}
+
+fn to_byte(channel: f32) -> u8 {
+    (channel.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn edge(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> f32 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, clear_color: [f32; 3], viewport: Viewport) -> Self {
+        let pixels = clear_color.map(to_byte).repeat((width * height) as usize);
+        Self {
+            width,
+            height,
+            pixels,
+            viewport,
+        }
+    }
+
+    fn to_screen(&self, position: [f32; 2]) -> (f32, f32) {
+        let position = self.viewport.to_ndc(position);
+        (
+            (position[0] + 1.0) / 2.0 * self.width as f32,
+            (1.0 - position[1]) / 2.0 * self.height as f32,
+        )
+    }
+
+    fn set_pixel(&mut self, x: i32, y: i32, color: [f32; 3]) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let idx = 3 * (y as usize * self.width as usize + x as usize);
+        self.pixels[idx..idx + 3].copy_from_slice(&color.map(to_byte));
+    }
+
+    /// Fills a triangle, interpolating the vertex colors like the GPU does.
+    pub fn fill_triangle(&mut self, vertices: [([f32; 2], [f32; 3]); 3]) {
+        let points = vertices.map(|(position, _)| self.to_screen(position));
+        let area = edge(points[0], points[1], points[2]);
+        if area.abs() < f32::EPSILON {
+            return;
+        }
+        let min_x = points.iter().map(|p| p.0).fold(f32::INFINITY, f32::min);
+        let max_x = points.iter().map(|p| p.0).fold(f32::NEG_INFINITY, f32::max);
+        let min_y = points.iter().map(|p| p.1).fold(f32::INFINITY, f32::min);
+        let max_y = points.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+        for y in (min_y.floor() as i32).max(0)..(max_y.ceil() as i32).min(self.height as i32) {
+            for x in (min_x.floor() as i32).max(0)..(max_x.ceil() as i32).min(self.width as i32) {
+                let p = (x as f32 + 0.5, y as f32 + 0.5);
+                let weights = [
+                    edge(points[1], points[2], p) / area,
+                    edge(points[2], points[0], p) / area,
+                    edge(points[0], points[1], p) / area,
+                ];
+                if weights.iter().any(|w| *w < 0.0) {
+                    continue;
+                }
+                let mut color = [0.0; 3];
+                for (weight, (_, vertex_color)) in weights.iter().zip(vertices) {
+                    for channel in 0..3 {
+                        color[channel] += weight * vertex_color[channel];
+                    }
+                }
+                self.set_pixel(x, y, color);
+            }
+        }
+    }
+
+    /// Draws a one pixel wide line segment.
+    pub fn draw_line(&mut self, from: [f32; 2], to: [f32; 2], color: [f32; 3]) {
+        let from = self.to_screen(from);
+        let to = self.to_screen(to);
+        let steps = (to.0 - from.0)
+            .abs()
+            .max((to.1 - from.1).abs())
+            .ceil()
+            .max(1.0) as i32;
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let x = from.0 + (to.0 - from.0) * t;
+            let y = from.1 + (to.1 - from.1) * t;
+            self.set_pixel(x.floor() as i32, y.floor() as i32, color);
+        }
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), png::EncodingError> {
+        let file = File::create(path)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.pixels)
+    }
+}
+
+/// Writes canvases as the frames of an endlessly looping animated GIF.
+pub struct GifWriter {
+    encoder: gif::Encoder<BufWriter<File>>,
+    delay: u16,
+}
+
+impl GifWriter {
+    pub fn create(
+        path: impl AsRef<Path>,
+        width: u32,
+        height: u32,
+        fps: f32,
+    ) -> Result<Self, gif::EncodingError> {
+        let file = File::create(path)?;
+        let mut encoder =
+            gif::Encoder::new(BufWriter::new(file), width as u16, height as u16, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        Ok(Self {
+            encoder,
+            // GIF delays are in hundredths of a second
+            delay: (100.0 / fps).round() as u16,
+        })
+    }
+
+    pub fn add_frame(&mut self, canvas: &Canvas) -> Result<(), gif::EncodingError> {
+        let mut frame = gif::Frame::from_rgb_speed(
+            canvas.width as u16,
+            canvas.height as u16,
+            &canvas.pixels,
+            10,
+        );
+        frame.delay = self.delay;
+        self.encoder.write_frame(&frame)
+    }
+}
@@ -0,0 +1,206 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cli::Args;
+
+use hw1_function_heatmap::colormap::Colormap;
+use hw1_function_heatmap::function::{DistanceMetric, NoiseKind, WorleyMode};
+use hw1_function_heatmap::grid::Dimensions;
+use hw1_function_heatmap::isoline::Levels;
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseSettings {
+    pub kind: NoiseKind,
+    /// Without it every run gets a random seed.
+    pub seed: Option<u64>,
+    pub lattice: Dimensions,
+    pub octaves: usize,
+    pub lacunarity: f32,
+    pub gain: f32,
+    pub tileable: bool,
+    pub worley: WorleyMode,
+    pub metric: DistanceMetric,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct GridSettings {
+    /// `[x0, x1, y0, y1]` of the visible part of the plane.
+    pub extent: [f32; 4],
+    pub cells: Dimensions,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(from = "IsolineFile")]
+pub struct IsolineSettings {
+    pub levels: Levels,
+    pub bands: bool,
+}
+
+/// `IsolineSettings` as read from a file, which may also be from before
+/// `levels`, with a `count` of quantiles instead.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct IsolineFile {
+    levels: Option<Levels>,
+    count: Option<usize>,
+    bands: Option<bool>,
+}
+
+impl From<IsolineFile> for IsolineSettings {
+    fn from(file: IsolineFile) -> Self {
+        let default = Self::default();
+        Self {
+            levels: file
+                .levels
+                .or(file.count.map(|count| Levels::Quantile { count }))
+                .unwrap_or(default.levels),
+            bands: file.bands.unwrap_or(default.bands),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorSettings {
+    pub colormap: Colormap,
+    /// Fixed `[min, max]`; without it the range of each frame is used.
+    pub range: Option<[f32; 2]>,
+    pub clip: f32,
+    pub clear_color: [f32; 3],
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReliefSettings {
+    pub enabled: bool,
+    pub azimuth: f32,
+    pub altitude: f32,
+    pub strength: f32,
+    pub exaggeration: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlaySettings {
+    pub arrows: bool,
+    pub lattice_lines: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnimationSettings {
+    /// How much faster than real time the field changes.
+    pub speed: f32,
+    /// Animation time in seconds the field has been advanced to.
+    pub time: f32,
+    pub frame_interval: u64,
+}
+
+/// Everything needed to reproduce a picture, as stored in `scene.toml`.
+/// Missing sections and keys take the defaults of the command line options,
+/// so a hand-written scene only needs what differs from them.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scene {
+    pub noise: NoiseSettings,
+    pub grid: GridSettings,
+    pub isolines: IsolineSettings,
+    pub colors: ColorSettings,
+    pub relief: ReliefSettings,
+    pub overlay: OverlaySettings,
+    pub animation: AnimationSettings,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Args::defaults().to_scene()
+    }
+}
+
+/// Defaults of the sections, taken from `Scene::default`.
+macro_rules! section_defaults {
+    ($($section:ident: $settings:ty),*) => {
+        $(
+            impl Default for $settings {
+                fn default() -> Self {
+                    Scene::default().$section
+                }
+            }
+        )*
+    };
+}
+
+section_defaults!(
+    noise: NoiseSettings,
+    grid: GridSettings,
+    isolines: IsolineSettings,
+    colors: ColorSettings,
+    relief: ReliefSettings,
+    overlay: OverlaySettings,
+    animation: AnimationSettings
+);
+
+impl Scene {
+    pub fn load(path: impl AsRef<Path>) -> Result<Scene, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("can't parse {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text = toml::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn a_saved_scene_loads_back() {
+        let mut args = Args::defaults();
+        args.seed = Some(i64::MAX as u64);
+        args.levels = Some(Levels::Interval {
+            interval: 0.1,
+            base: 0.05,
+        });
+        let scene = args.to_scene();
+        let path = std::env::temp_dir().join(format!("scene_{}.toml", std::process::id()));
+        scene.save(&path).unwrap();
+        let loaded = Scene::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.noise.seed, Some(i64::MAX as u64));
+        assert_eq!(loaded.isolines.levels, scene.isolines.levels);
+        assert_eq!(
+            toml::to_string(&loaded).unwrap(),
+            toml::to_string(&scene).unwrap()
+        );
+    }
+
+    #[test]
+    fn an_interval_without_a_base_starts_at_zero() {
+        let scene: Scene =
+            toml::from_str("[isolines.levels]\nmode = \"interval\"\ninterval = 0.25\n").unwrap();
+        assert_eq!(
+            scene.isolines.levels,
+            Levels::Interval {
+                interval: 0.25,
+                base: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn seeds_that_toml_cant_store_are_rejected() {
+        let seed = (i64::MAX as u64 + 1).to_string();
+        assert!(Args::try_parse_from(["heatmap", "--seed", &seed]).is_err());
+    }
+}
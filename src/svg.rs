@@ -0,0 +1,69 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::grid::Grid;
+use crate::isoline::Isolines;
+
+/// Stroke color of the `level`-th of `count` levels, going from blue for the
+/// lowest level to red for the highest one.
+fn level_color(level: usize, count: usize) -> String {
+    let t = if count > 1 {
+        level as f32 / (count - 1) as f32
+    } else {
+        0.5
+    };
+    let [r, g, b] = [t, 1.0 - (2.0 * t - 1.0).abs(), 1.0 - t].map(|c| (c * 255.0).round() as u8);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Writes the isolines found by the last `Isolines::process` as an SVG image.
+/// Every level becomes a `<g>` with a single `<path>`, and coordinates are the
+/// world coordinates of `grid`.
+pub fn write_svg(
+    path: impl AsRef<Path>,
+    isolines: &Isolines,
+    grid: &Grid,
+    width: u32,
+    height: u32,
+) -> std::io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+        width,
+        height,
+        grid.x0,
+        -grid.y1,
+        grid.x1 - grid.x0,
+        grid.y1 - grid.y0,
+    )?;
+    writeln!(
+        out,
+        r#"  <g transform="scale(1,-1)" fill="none" stroke-width="1">"#
+    )?;
+    let levels = isolines.get_polylines();
+    for (level, (c, polylines)) in levels.iter().enumerate() {
+        writeln!(
+            out,
+            r#"    <g class="isoline" data-level="{}" stroke="{}">"#,
+            c,
+            level_color(level, levels.len()),
+        )?;
+        write!(out, r#"      <path vector-effect="non-scaling-stroke" d=""#)?;
+        for polyline in polylines {
+            for (idx, point) in polyline.points.iter().enumerate() {
+                let command = if idx == 0 { 'M' } else { 'L' };
+                write!(out, "{}{} {}", command, point[0], point[1])?;
+            }
+            if polyline.closed {
+                write!(out, "Z")?;
+            }
+        }
+        writeln!(out, r#""/>"#)?;
+        writeln!(out, "    </g>")?;
+    }
+    writeln!(out, "  </g>")?;
+    writeln!(out, "</svg>")?;
+    out.flush()
+}
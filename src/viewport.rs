@@ -0,0 +1,111 @@
+use crate::grid::{Dimensions, Grid};
+
+/// Narrowest side of a zoomed viewport, in world units.
+const MIN_SIZE: f32 = 1e-3;
+/// Widest side of a zoomed viewport, in world units.
+const MAX_SIZE: f32 = 100.0;
+
+/// The rectangle of world coordinates shown in the window. It keeps the
+/// aspect ratio of the window, so the field is never stretched.
+#[derive(Clone, Copy)]
+pub struct Viewport {
+    pub x0: f32,
+    pub x1: f32,
+    pub y0: f32,
+    pub y1: f32,
+}
+
+impl Viewport {
+    /// The smallest viewport around `extent` (`[x0, x1, y0, y1]`) that has the
+    /// aspect ratio of `window`, with the same center.
+    pub fn fit(extent: [f32; 4], window: Dimensions) -> Self {
+        let [x0, x1, y0, y1] = extent;
+        let window_ratio = window.h as f32 / window.w as f32;
+        let half_width = ((x1 - x0) / 2.0).max((y1 - y0) / 2.0 / window_ratio);
+        let half_height = half_width * window_ratio;
+        let center = [(x0 + x1) / 2.0, (y0 + y1) / 2.0];
+        Self {
+            x0: center[0] - half_width,
+            x1: center[0] + half_width,
+            y0: center[1] - half_height,
+            y1: center[1] + half_height,
+        }
+    }
+
+    pub fn get_extent(&self) -> [f32; 4] {
+        [self.x0, self.x1, self.y0, self.y1]
+    }
+
+    /// A grid of `dimensions` cells covering the viewport.
+    pub fn get_grid(&self, dimensions: Dimensions) -> Grid {
+        Grid::new(self.x0, self.x1, self.y0, self.y1, dimensions)
+    }
+
+    /// The `view` uniform of the shaders: NDC are `(position - view.xy) * view.zw`.
+    pub fn get_transform(&self) -> [f32; 4] {
+        [
+            (self.x0 + self.x1) / 2.0,
+            (self.y0 + self.y1) / 2.0,
+            2.0 / (self.x1 - self.x0),
+            2.0 / (self.y1 - self.y0),
+        ]
+    }
+
+    pub fn to_ndc(&self, point: [f32; 2]) -> [f32; 2] {
+        let [cx, cy, sx, sy] = self.get_transform();
+        [(point[0] - cx) * sx, (point[1] - cy) * sy]
+    }
+
+    /// World coordinates of a pixel of `window`, counted from the top left corner.
+    pub fn to_world(&self, pixel: (f64, f64), window: Dimensions) -> [f32; 2] {
+        let tx = pixel.0 as f32 / window.w as f32;
+        let ty = pixel.1 as f32 / window.h as f32;
+        [
+            self.x0 + tx * (self.x1 - self.x0),
+            self.y1 - ty * (self.y1 - self.y0),
+        ]
+    }
+
+    /// Moves the viewport so that the field follows a mouse drag of `delta` pixels.
+    pub fn pan(&mut self, delta: (f64, f64), window: Dimensions) {
+        let dx = -(delta.0 as f32) / window.w as f32 * (self.x1 - self.x0);
+        let dy = delta.1 as f32 / window.h as f32 * (self.y1 - self.y0);
+        self.x0 += dx;
+        self.x1 += dx;
+        self.y0 += dy;
+        self.y1 += dy;
+    }
+
+    /// Scales the viewport by `factor` keeping `center` at the same place on
+    /// screen; factors below 1 zoom in. The size stays within `MIN_SIZE` and
+    /// `MAX_SIZE`, so zooming can't go past f32 precision or make the lattice
+    /// overlay unboundedly dense.
+    pub fn zoom(&mut self, factor: f32, center: [f32; 2]) {
+        let (width, height) = (self.x1 - self.x0, self.y1 - self.y0);
+        // only limit zooming further out (or in), an extent already past the
+        // limits may still zoom back
+        let factor = if factor > 1.0 {
+            factor.min((MAX_SIZE / width.max(height)).max(1.0))
+        } else {
+            factor.max((MIN_SIZE / width.min(height)).min(1.0))
+        };
+        self.x0 = center[0] + (self.x0 - center[0]) * factor;
+        self.x1 = center[0] + (self.x1 - center[0]) * factor;
+        self.y0 = center[1] + (self.y0 - center[1]) * factor;
+        self.y1 = center[1] + (self.y1 - center[1]) * factor;
+    }
+
+    /// The viewport for the window resized from `old` to `new` pixels: the
+    /// center stays and so does the size of a pixel in world coordinates.
+    pub fn resize(&self, old: Dimensions, new: Dimensions) -> Self {
+        let half_width = (self.x1 - self.x0) / 2.0 * new.w as f32 / old.w as f32;
+        let half_height = (self.y1 - self.y0) / 2.0 * new.h as f32 / old.h as f32;
+        let [cx, cy, _, _] = self.get_transform();
+        Self {
+            x0: cx - half_width,
+            x1: cx + half_width,
+            y0: cy - half_height,
+            y1: cy + half_height,
+        }
+    }
+}